@@ -0,0 +1,460 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::binary::split_content;
+use crate::fstree::ROOT;
+use crate::glob1env::{is_vault_file, Directory, File, FsEntry, Glob1Env};
+use crate::metadata::Metadata;
+use crate::storage;
+use crate::vpath::validate_name;
+
+/// Fichier facultatif à la racine de l'archive, qui garde les drapeaux et les dates
+pub const MANIFEST: &str = ".glob1env.json";
+
+/// Taille maximale du contenu décompressé d'une archive importée
+const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Par chemin dans l'archive
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    #[serde(default)]
+    system: bool,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+/// Entrée à plat d'une archive ; `content` vaut `None` pour un répertoire
+struct ArchiveItem {
+    path: String,
+    content: Option<Vec<u8>>,
+    modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveReport {
+    pub files: usize,
+    pub directories: usize,
+    /// Entrées laissées de côté, avec la raison
+    pub skipped: Vec<String>,
+    /// Entrées système du manifeste importées sans leur drapeau
+    pub system_ignored: usize,
+}
+
+impl ArchiveFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Ok(ArchiveFormat::Zip)
+        } else {
+            Err(format!("Format d'archive inconnu : '{}' (.tar, .tar.gz, .tgz, .zip)", path.display()))
+        }
+    }
+}
+
+impl ArchiveReport {
+    /// Lignes affichées après un export
+    pub fn exported(&self, host: &Path) -> Vec<String> {
+        self.lines(format!("Exported {} to `{}`", self.counts(), host.display()))
+    }
+
+    /// Lignes affichées après un import
+    pub fn imported(&self, dir: &Path) -> Vec<String> {
+        self.lines(format!("Imported {} into `{}`", self.counts(), dir.display()))
+    }
+
+    fn counts(&self) -> String {
+        format!("{} files and {} folders", self.files, self.directories)
+    }
+
+    fn lines(&self, summary: String) -> Vec<String> {
+        let mut lines = vec![summary];
+        lines.extend(self.skipped.iter().map(|s| format!("  skipped {s}")));
+        if self.system_ignored > 0 {
+            lines.push(format!("  {} system flags not kept (import with --keep-system to keep them)", self.system_ignored));
+        }
+        lines
+    }
+}
+
+fn write_tar(items: &[ArchiveItem], writer: impl Write) -> std::io::Result<()> {
+    let mut builder = tar::Builder::new(writer);
+    for item in items {
+        let mut header = tar::Header::new_gnu();
+        let data = item.content.as_deref().unwrap_or_default();
+        match item.content {
+            Some(_) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(0o644);
+            }
+            None => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+            }
+        }
+        header.set_size(data.len() as u64);
+        header.set_mtime(item.modified.map(|t| t.timestamp().max(0) as u64).unwrap_or_default());
+        builder.append_data(&mut header, &item.path, data)?;
+    }
+    builder.into_inner()?.flush()
+}
+
+fn write_zip(items: &[ArchiveItem]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for item in items {
+        let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        // Les dates zip sont locales, à deux secondes près
+        if let Some(time) = item.modified.map(|t| t.with_timezone(&Local))
+            && let Ok(time) = zip::DateTime::from_date_and_time(
+                time.year() as u16, time.month() as u8, time.day() as u8,
+                time.hour() as u8, time.minute() as u8, (time.second() / 2 * 2) as u8,
+            )
+        {
+            options = options.last_modified_time(time);
+        }
+        match &item.content {
+            Some(data) => {
+                writer.start_file(item.path.as_str(), options)?;
+                writer.write_all(data)?;
+            }
+            None => writer.add_directory(item.path.as_str(), options)?,
+        }
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Chemin d'une entrée d'archive, refusé s'il est absolu ou remonte avec `..`
+fn archive_path(path: &Path) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => parts.push(name.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => return Err(format!("Chemin refusé dans l'archive : '{}'", path.display())),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn read_tar(reader: impl Read) -> Result<Vec<ArchiveItem>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut items = Vec::new();
+    let mut total = 0;
+    for entry in archive.entries().map_err(|e| format!("Archive illisible : {}", e))? {
+        let mut entry = entry.map_err(|e| format!("Archive illisible : {}", e))?;
+        let path = archive_path(&entry.path().map_err(|e| format!("Archive illisible : {}", e))?)?;
+        let modified = entry.header().mtime().ok().and_then(|t| DateTime::from_timestamp(t as i64, 0));
+        let content = match entry.header().entry_type() {
+            tar::EntryType::Directory => None,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                total += entry.size();
+                if total > MAX_ARCHIVE_SIZE {
+                    return Err("Archive trop volumineuse".into());
+                }
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).map_err(|e| format!("'{}' : {}", path, e))?;
+                Some(data)
+            }
+            // Liens, périphériques et métadonnées étendues n'ont pas d'équivalent
+            _ => continue,
+        };
+        if !path.is_empty() {
+            items.push(ArchiveItem { path, content, modified });
+        }
+    }
+    Ok(items)
+}
+
+fn read_zip(data: Vec<u8>) -> Result<Vec<ArchiveItem>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Archive illisible : {}", e))?;
+    let mut items = Vec::new();
+    let mut total = 0;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| format!("Archive illisible : {}", e))?;
+        let path = archive_path(Path::new(file.name()))?;
+        let modified = file.last_modified()
+            .and_then(|t| NaiveDate::from_ymd_opt(t.year() as i32, t.month() as u32, t.day() as u32)?
+                .and_hms_opt(t.hour() as u32, t.minute() as u32, t.second() as u32))
+            .and_then(|t| Local.from_local_datetime(&t).single())
+            .map(|t| t.with_timezone(&Utc));
+        let content = if file.is_dir() {
+            None
+        } else {
+            total += file.size();
+            if total > MAX_ARCHIVE_SIZE {
+                return Err("Archive trop volumineuse".into());
+            }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data).map_err(|e| format!("'{}' : {}", path, e))?;
+            Some(data)
+        };
+        if !path.is_empty() {
+            items.push(ArchiveItem { path, content, modified });
+        }
+    }
+    Ok(items)
+}
+
+/// Place `entry` dans `content` au chemin `parts`, en créant les répertoires intermédiaires.
+/// Un répertoire déjà créé implicitement prend les drapeaux et dates de son entrée.
+fn place(content: &mut Vec<FsEntry>, parts: &[&str], entry: FsEntry) -> Result<(), String> {
+    let (name, rest) = parts.split_first().ok_or("Chemin vide")?;
+    validate_name(name)?;
+    let existing = content.iter().position(|e| e.name() == *name);
+
+    if rest.is_empty() {
+        return match (existing.map(|i| &mut content[i]), entry) {
+            (None, entry) => {
+                content.push(entry);
+                Ok(())
+            }
+            (Some(FsEntry::Directory(dir)), FsEntry::Directory(new)) => {
+                dir.system = new.system;
+                dir.metadata = new.metadata;
+                Ok(())
+            }
+            (Some(_), _) => Err(format!("'{}' apparaît deux fois dans l'archive", name)),
+        };
+    }
+
+    let index = match existing {
+        Some(index) => index,
+        None => {
+            content.push(FsEntry::Directory(Directory {
+                name: name.to_string(),
+                content: Vec::new(),
+                system: false,
+                metadata: Metadata::now(),
+                mount: None,
+            }));
+            content.len() - 1
+        }
+    };
+    match &mut content[index] {
+        FsEntry::Directory(dir) => place(&mut dir.content, rest, entry),
+        FsEntry::File(_) => Err(format!("'{}' est un fichier dans l'archive", name)),
+    }
+}
+
+impl Glob1Env {
+    /// Enregistre le répertoire `dir` dans l'archive `host`. Les fichiers `.pwd` et
+    /// les montages ne sont pas exportés.
+    pub fn export_archive(&self, dir: &Path, host: &Path, manifest: bool) -> Result<ArchiveReport, String> {
+        let format = ArchiveFormat::from_path(host)?;
+        let id = self.lookup_dir(dir)?;
+        let FsEntry::Directory(root) = self.tree.entry(id) else {
+            return Err(format!("Pas un répertoire : '{}'", dir.display()));
+        };
+
+        let mut items = Vec::new();
+        let mut report = ArchiveReport::default();
+        let mut entries = BTreeMap::new();
+        let mut stack: Vec<(String, FsEntry)> = match id {
+            ROOT => root.content.into_iter().rev().map(|e| (e.name().to_string(), e)).collect(),
+            _ => vec![(root.name.clone(), FsEntry::Directory(root))],
+        };
+        while let Some((path, entry)) = stack.pop() {
+            let (system, metadata) = match entry {
+                FsEntry::Directory(d) if d.mount.is_some() => {
+                    report.skipped.push(format!("{path} (mount)"));
+                    continue;
+                }
+                FsEntry::File(f) if is_vault_file(&f.name) => {
+                    report.skipped.push(format!("{path} (vault file)"));
+                    continue;
+                }
+                FsEntry::Directory(d) => {
+                    report.directories += 1;
+                    stack.extend(d.content.into_iter().rev().map(|e| (format!("{path}/{}", e.name()), e)));
+                    items.push(ArchiveItem { path: path.clone(), content: None, modified: d.metadata.modified });
+                    (d.system, d.metadata)
+                }
+                FsEntry::File(f) => {
+                    report.files += 1;
+                    let bytes = f.data.unwrap_or_else(|| f.content.into_bytes());
+                    items.push(ArchiveItem { path: path.clone(), content: Some(bytes), modified: f.metadata.modified });
+                    (f.system, f.metadata)
+                }
+            };
+            entries.insert(path, ManifestEntry { system, metadata });
+        }
+
+        if manifest {
+            let manifest = Manifest { version: 1, entries };
+            let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+            items.insert(0, ArchiveItem { path: MANIFEST.to_string(), content: Some(json), modified: Some(Utc::now()) });
+        }
+
+        let data = match format {
+            ArchiveFormat::Tar => {
+                let mut data = Vec::new();
+                write_tar(&items, &mut data).map(|_| data).map_err(|e| e.to_string())
+            }
+            ArchiveFormat::TarGz => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                write_tar(&items, &mut encoder).and_then(|_| encoder.finish()).map_err(|e| e.to_string())
+            }
+            ArchiveFormat::Zip => write_zip(&items).map_err(|e| e.to_string()),
+        }.map_err(|e| format!("Création de l'archive impossible : {}", e))?;
+        storage::write_atomic(host, &data)?;
+        Ok(report)
+    }
+
+    /// Ajoute le contenu de l'archive `host` dans `dir`, créé s'il n'existe pas.
+    /// Rien n'est ajouté si une entrée de premier niveau existe déjà. Les drapeaux `system`
+    /// du manifeste ne sont repris qu'avec `keep_system` : ces entrées deviennent indélébiles.
+    pub fn import_archive(&mut self, host: &Path, dir: &Path, keep_system: bool) -> Result<ArchiveReport, String> {
+        let format = ArchiveFormat::from_path(host)?;
+        let data = std::fs::read(host).map_err(|e| format!("Lecture de '{}' impossible : {}", host.display(), e))?;
+        let mut items = match format {
+            ArchiveFormat::Tar => read_tar(data.as_slice())?,
+            ArchiveFormat::TarGz => read_tar(GzDecoder::new(data.as_slice()))?,
+            ArchiveFormat::Zip => read_zip(data)?,
+        };
+
+        let manifest = match items.iter().position(|item| item.path == MANIFEST) {
+            Some(index) => {
+                let json = items.remove(index).content.unwrap_or_default();
+                serde_json::from_slice::<Manifest>(&json).map_err(|e| format!("{} invalide : {}", MANIFEST, e))?
+            }
+            None => Manifest::default(),
+        };
+
+        let mut report = ArchiveReport::default();
+        let mut content = Vec::new();
+        for item in items {
+            let name = item.path.rsplit('/').next().unwrap_or_default().to_string();
+            let (system, mut metadata) = match manifest.entries.get(&item.path) {
+                Some(entry) => (entry.system, entry.metadata.clone()),
+                None => (false, Metadata { modified: item.modified.or(Some(Utc::now())), ..Metadata::now() }),
+            };
+            if system && !keep_system {
+                report.system_ignored += 1;
+            }
+            let system = system && keep_system;
+            metadata.trashed_from = None;
+            metadata.deleted = None;
+
+            let entry = match item.content {
+                None => {
+                    report.directories += 1;
+                    FsEntry::Directory(Directory { name, content: Vec::new(), system, metadata, mount: None })
+                }
+                Some(_) if is_vault_file(&name) => {
+                    report.skipped.push(format!("{} (vault file)", item.path));
+                    continue;
+                }
+                Some(bytes) => {
+                    report.files += 1;
+                    let (content, data) = split_content(bytes);
+                    FsEntry::File(File { name, content, system, metadata, history: Vec::new(), data })
+                }
+            };
+            let parts: Vec<&str> = item.path.split('/').filter(|p| !p.is_empty()).collect();
+            place(&mut content, &parts, entry)?;
+        }
+
+        if !self.exists(dir) {
+            self.insert_entry(dir, FsEntry::Directory(Directory {
+                name: String::new(),
+                content,
+                system: false,
+                metadata: Metadata::now(),
+                mount: None,
+            }))?;
+            return Ok(report);
+        }
+        let id = self.lookup_dir(dir)?;
+        self.writable_dir(id)?;
+        if let Some(existing) = content.iter().find(|entry| self.exists(&dir.join(entry.name()))) {
+            return Err(format!("'{}' existe déjà", dir.join(existing.name()).display()));
+        }
+        // Tout ou rien : une insertion refusée retire celles qui l'ont précédée
+        let mut inserted: Vec<PathBuf> = Vec::new();
+        for entry in content {
+            let path = dir.join(entry.name());
+            if let Err(e) = self.insert_entry(&path, entry) {
+                for path in inserted.iter().rev() {
+                    if let Some(id) = self.lookup(path) {
+                        self.tree.remove(id);
+                    }
+                    self.undo_stack.pop_back();
+                }
+                return Err(e);
+            }
+            inserted.push(path);
+        }
+        Ok(report)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Origin;
+    use crate::vault::Vault;
+
+    /// Archive de `/src` dont `keep.txt` est un fichier système
+    fn archive(dir: &Path) -> (Glob1Env, PathBuf) {
+        let mut env = Glob1Env::new(None, Vault::default());
+        env.mkdir(Path::new("/src")).unwrap();
+        env.wr(Path::new("/src/keep.txt"), "k".to_string(), Origin::Terminal).unwrap();
+        let id = env.lookup(Path::new("/src/keep.txt")).unwrap();
+        if let crate::fstree::Node::File(f) = env.tree.node_mut(id) {
+            f.system = true;
+        }
+        let host = dir.join("src.tar.gz");
+        env.export_archive(Path::new("/src"), &host, true).unwrap();
+        (env, host)
+    }
+
+    #[test]
+    fn system_flags_need_keep_system() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (mut env, host) = archive(tmp.path());
+
+        let report = env.import_archive(&host, Path::new("/plain"), false).unwrap();
+        assert_eq!(report.system_ignored, 1);
+        assert!(!env.file(Path::new("/plain/src/keep.txt")).unwrap().system);
+
+        let report = env.import_archive(&host, Path::new("/kept"), true).unwrap();
+        assert_eq!(report.system_ignored, 0);
+        assert!(env.file(Path::new("/kept/src/keep.txt")).unwrap().system);
+    }
+
+    #[test]
+    fn import_does_not_overwrite_existing_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (mut env, host) = archive(tmp.path());
+        env.mkdir(Path::new("/dst")).unwrap();
+        env.mkdir(Path::new("/dst/src")).unwrap();
+        env.wr(Path::new("/dst/src/keep.txt"), "mine".to_string(), Origin::Terminal).unwrap();
+
+        assert!(env.import_archive(&host, Path::new("/dst"), false).is_err());
+        assert_eq!(env.rd(Path::new("/dst/src/keep.txt")).unwrap(), "mine");
+    }
+}
@@ -0,0 +1,121 @@
+/// Découpe une ligne de commande façon shell :
+/// - les espaces séparent les arguments (plusieurs espaces consécutifs n'en créent pas de vides)
+/// - '...' garde le texte tel quel
+/// - "..." accepte les échappements \" et \\
+/// - \x hors guillemets échappe n'importe quel caractère
+/// - "" ou '' produit un argument vide
+pub fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = input.char_indices();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => current.push(c),
+                        None => return Err(format!("Unterminated single quote (column {})", pos + 1)),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => current.push(c),
+                            Some((_, c)) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(format!("Unterminated double quote (column {})", pos + 1)),
+                        },
+                        Some((_, c)) => current.push(c),
+                        None => return Err(format!("Unterminated double quote (column {})", pos + 1)),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some((_, c)) => current.push(c),
+                    None => return Err(format!("Trailing backslash (column {})", pos + 1)),
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        split_args(input).unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(args("env ls /a"), ["env", "ls", "/a"]);
+        assert_eq!(args("  env   ls\t/a  "), ["env", "ls", "/a"]);
+        assert!(args("").is_empty());
+        assert!(args("   ").is_empty());
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(args("wr 'a b' 'c\\d'"), ["wr", "a b", "c\\d"]);
+        assert_eq!(args("'\"'"), ["\""]);
+        assert_eq!(args("a'b c'd"), ["ab cd"]);
+    }
+
+    #[test]
+    fn double_quotes_escape_quote_and_backslash() {
+        assert_eq!(args(r#""a \"b\" c""#), [r#"a "b" c"#]);
+        assert_eq!(args(r#""a\\b""#), [r"a\b"]);
+        assert_eq!(args(r#""a\nb""#), [r"a\nb"]);
+        assert_eq!(args(r#""it's""#), ["it's"]);
+    }
+
+    #[test]
+    fn backslash_escapes_outside_quotes() {
+        assert_eq!(args(r"a\ b c"), ["a b", "c"]);
+        assert_eq!(args(r"\'x\'"), ["'x'"]);
+        assert_eq!(args(r"\\"), ["\\"]);
+    }
+
+    #[test]
+    fn empty_quotes_give_empty_argument() {
+        assert_eq!(args("wr a ''"), ["wr", "a", ""]);
+        assert_eq!(args(r#""" x"#), ["", "x"]);
+    }
+
+    #[test]
+    fn unterminated_input_is_an_error() {
+        assert_eq!(split_args("a 'b"), Err("Unterminated single quote (column 3)".to_string()));
+        assert_eq!(split_args("a \"b"), Err("Unterminated double quote (column 3)".to_string()));
+        assert_eq!(split_args("\"b\\"), Err("Unterminated double quote (column 1)".to_string()));
+        assert_eq!(split_args("a\\"), Err("Trailing backslash (column 2)".to_string()));
+    }
+}
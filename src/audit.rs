@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use chrono::{Duration, Utc};
+use sha1::{Digest, Sha1};
+use crate::pwdfile::Credential;
+
+/// En dessous, un mot de passe est signalé comme faible
+pub const WEAK_ENTROPY_BITS: f64 = 60.0;
+/// Distance d'édition maximale entre deux secrets « presque identiques »
+const NEAR_DUPLICATE_DISTANCE: usize = 2;
+
+#[derive(Clone, Debug)]
+pub struct AuditOptions {
+    pub max_age_days: i64,
+    /// Fichier `HASH:COUNT` ou répertoire de fichiers `PREFIX` contenant des lignes `SUFFIX:COUNT`
+    pub breached: Option<PathBuf>,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            max_age_days: 365,
+            breached: None,
+        }
+    }
+}
+
+impl AuditOptions {
+    /// `[--max-age <days>] [--breached <host path>]`, le reste étant renvoyé tel quel
+    pub fn from_args<'a>(args: &[&'a str]) -> Result<(Self, Vec<&'a str>), String> {
+        let mut options = Self::default();
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match *arg {
+                "--max-age" => {
+                    let days = args.next().ok_or("--max-age expects a number of days")?;
+                    options.max_age_days = days.parse().map_err(|_| format!("Invalid number of days: '{}'", days))?;
+                }
+                "--breached" => {
+                    let path = args.next().ok_or("--breached expects a path")?;
+                    options.breached = Some(crate::import_export::expand_home(path));
+                }
+                other => rest.push(other),
+            }
+        }
+
+        Ok((options, rest))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub file: PathBuf,
+    pub service: String,
+    pub username: String,
+    pub issues: Vec<String>,
+}
+
+impl Finding {
+    pub fn label(&self) -> String {
+        let account = if self.username.is_empty() {
+            self.service.clone()
+        } else {
+            format!("{} ({})", self.service, self.username)
+        };
+        format!("{} in {}: {}", account, self.file.display(), self.issues.join(", "))
+    }
+}
+
+/// Entropie estimée en bits : longueur × log2 de la taille des classes de caractères utilisées
+pub fn entropy_bits(secret: &str) -> f64 {
+    let mut pool = 0;
+    if secret.chars().any(|c| c.is_ascii_lowercase()) { pool += 26; }
+    if secret.chars().any(|c| c.is_ascii_uppercase()) { pool += 26; }
+    if secret.chars().any(|c| c.is_ascii_digit()) { pool += 10; }
+    if secret.chars().any(|c| c.is_ascii_punctuation() || c == ' ') { pool += 33; }
+    if !secret.is_ascii() { pool += 100; }
+
+    if pool == 0 {
+        return 0.0;
+    }
+    secret.chars().count() as f64 * (pool as f64).log2()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Secrets qui ne diffèrent que par la casse, un suffixe numérique ou quelques caractères
+fn near_duplicates(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| s.to_lowercase().trim_end_matches(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation()).to_string();
+    let (na, nb) = (normalize(a), normalize(b));
+    (!na.is_empty() && na == nb) || (a.len().min(b.len()) >= 8 && levenshtein(a, b) <= NEAR_DUPLICATE_DISTANCE)
+}
+
+fn sha1_hex(secret: &str) -> String {
+    Sha1::digest(secret.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Lit les lignes `HASH:COUNT` de `file` et note le compte de celles que `wanted` reconnaît
+fn scan(file: &Path, counts: &mut HashMap<String, u64>, wanted: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+    let reader = std::fs::File::open(file)
+        .map(BufReader::new)
+        .map_err(|e| format!("Lecture de '{}' impossible : {}", file.display(), e))?;
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let (candidate, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+        if let Some(hash) = wanted(&candidate.to_ascii_uppercase()) {
+            counts.insert(hash, count.trim().parse().unwrap_or(1));
+        }
+    }
+    Ok(())
+}
+
+/// Nombre d'apparitions du SHA-1 de chaque secret dans la liste de fuites, par hash.
+/// La liste (ou chaque fichier de préfixe utile) n'est parcourue qu'une fois par audit.
+fn breach_counts(list: &Path, secrets: &[&str]) -> Result<HashMap<String, u64>, String> {
+    let hashes: HashSet<String> = secrets.iter().map(|secret| sha1_hex(secret)).collect();
+    let mut counts = HashMap::new();
+
+    if list.is_dir() {
+        let mut by_prefix: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for hash in &hashes {
+            let (prefix, suffix) = hash.split_at(5);
+            by_prefix.entry(prefix).or_default().insert(suffix);
+        }
+        for (prefix, suffixes) in by_prefix {
+            let file = [list.join(prefix), list.join(format!("{prefix}.txt"))].into_iter().find(|p| p.exists());
+            if let Some(file) = file {
+                scan(&file, &mut counts, |suffix| suffixes.contains(suffix).then(|| format!("{prefix}{suffix}")))?;
+            }
+        }
+    } else {
+        scan(list, &mut counts, |hash| hashes.contains(hash).then(|| hash.to_string()))?;
+    }
+    Ok(counts)
+}
+
+/// Un `Finding` par identifiant ayant au moins un problème, dans l'ordre des fichiers
+pub fn audit(credentials: &[(PathBuf, Credential)], options: &AuditOptions) -> Result<Vec<Finding>, String> {
+    let max_age = Duration::days(options.max_age_days);
+    let breaches = match &options.breached {
+        Some(list) => {
+            let secrets: Vec<&str> = credentials.iter().map(|(_, c)| c.secret.as_str()).filter(|s| !s.is_empty()).collect();
+            Some(breach_counts(list, &secrets)?)
+        }
+        None => None,
+    };
+
+    let mut findings = Vec::new();
+    for (index, (file, credential)) in credentials.iter().enumerate() {
+        let secret = credential.secret.as_str();
+        let mut issues = Vec::new();
+
+        if secret.is_empty() {
+            issues.push("empty secret".to_string());
+        } else {
+            let bits = entropy_bits(secret);
+            if bits < WEAK_ENTROPY_BITS {
+                issues.push(format!("weak ({:.0} bits)", bits));
+            }
+
+            let others = |matches: &dyn Fn(&Credential) -> bool| -> Vec<String> {
+                credentials.iter().enumerate()
+                    .filter(|(other, (_, c))| *other != index && matches(c))
+                    .map(|(_, (_, c))| c.service.clone())
+                    .collect()
+            };
+            let reused = others(&|c| c.secret == secret);
+            if !reused.is_empty() {
+                issues.push(format!("reused by {}", reused.join(", ")));
+            }
+            let similar = others(&|c| !c.secret.is_empty() && c.secret != secret && near_duplicates(&c.secret, secret));
+            if !similar.is_empty() {
+                issues.push(format!("similar to {}", similar.join(", ")));
+            }
+
+            if let Some(&count) = breaches.as_ref().and_then(|breaches| breaches.get(&sha1_hex(secret)))
+                && count > 0
+            {
+                issues.push(format!("breached ({} times)", count));
+            }
+        }
+
+        match credential.updated.or(credential.created) {
+            Some(date) if Utc::now() - date > max_age => {
+                issues.push(format!("{} days old", (Utc::now() - date).num_days()));
+            }
+            Some(_) => {}
+            None => issues.push("no date".to_string()),
+        }
+
+        if !issues.is_empty() {
+            findings.push(Finding {
+                file: file.clone(),
+                service: credential.service.clone(),
+                username: credential.username.clone(),
+                issues,
+            });
+        }
+    }
+
+    Ok(findings)
+}
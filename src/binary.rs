@@ -0,0 +1,224 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText, TextureHandle, TextureOptions};
+use crate::glob1env::{File, FsEntry, Glob1Env};
+use crate::history::Origin;
+use crate::metadata::Metadata;
+use crate::mount::{Backend, HostDir};
+
+/// Octets par ligne du dump hexadécimal
+const BYTES_PER_LINE: usize = 16;
+/// Lignes par page du dump hexadécimal
+const LINES_PER_PAGE: usize = 32;
+const PAGE_SIZE: usize = BYTES_PER_LINE * LINES_PER_PAGE;
+
+/// Type d'un fichier, reconnu à ses premiers octets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileKind {
+    Text,
+    Png,
+    Jpeg,
+    Gif,
+    Pdf,
+    Binary,
+}
+
+/// Image décodée du fichier affiché, gardée tant que son contenu ne change pas
+#[derive(Clone)]
+pub struct ImagePreview {
+    path: PathBuf,
+    size: usize,
+    modified: Option<DateTime<Utc>>,
+    texture: Result<TextureHandle, String>,
+}
+
+impl fmt::Debug for ImagePreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImagePreview").field("path", &self.path).field("size", &self.size).finish()
+    }
+}
+
+impl FileKind {
+    pub fn detect(data: &[u8]) -> Self {
+        match data {
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => FileKind::Png,
+            [0xFF, 0xD8, 0xFF, ..] => FileKind::Jpeg,
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => FileKind::Gif,
+            [b'%', b'P', b'D', b'F', b'-', ..] => FileKind::Pdf,
+            _ if std::str::from_utf8(data).is_ok() => FileKind::Text,
+            _ => FileKind::Binary,
+        }
+    }
+
+    pub fn of(file: &File) -> Self {
+        match &file.data {
+            Some(data) => Self::detect(data),
+            None => FileKind::Text,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileKind::Text => "text",
+            FileKind::Png => "PNG image",
+            FileKind::Jpeg => "JPEG image",
+            FileKind::Gif => "GIF image",
+            FileKind::Pdf => "PDF document",
+            FileKind::Binary => "binary",
+        }
+    }
+
+    pub fn is_image(self) -> bool {
+        matches!(self, FileKind::Png | FileKind::Jpeg | FileKind::Gif)
+    }
+}
+
+/// Sépare un contenu lu sur l'hôte en texte ou en octets
+pub fn split_content(data: Vec<u8>) -> (String, Option<Vec<u8>>) {
+    match String::from_utf8(data) {
+        Ok(text) => (text, None),
+        Err(e) => (String::new(), Some(e.into_bytes())),
+    }
+}
+
+/// Octets d'un fichier binaire, en base64 dans la sauvegarde
+pub mod base64_data {
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match data {
+            Some(data) => serializer.serialize_some(&BASE64.encode(data)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| BASE64.decode(text).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Ligne du dump : décalage, octets en hexadécimal et en ASCII
+fn hex_line(offset: usize, bytes: &[u8]) -> String {
+    let hex: Vec<String> = (0..BYTES_PER_LINE)
+        .map(|i| bytes.get(i).map(|b| format!("{:02x}", b)).unwrap_or_else(|| "  ".to_string()))
+        .collect();
+    let ascii: String = bytes.iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect();
+    format!("{:08x}  {}  {}  |{}|", offset, hex[..8].join(" "), hex[8..].join(" "), ascii)
+}
+
+fn decode_image(ctx: &egui::Context, path: &Path, data: &[u8]) -> Result<TextureHandle, String> {
+    // Pour un GIF animé, seule la première image est affichée
+    let image = image::load_from_memory(data)
+        .map_err(|e| format!("Image illisible : {}", e))?
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Ok(ctx.load_texture(path.to_string_lossy(), color_image, TextureOptions::LINEAR))
+}
+
+impl Glob1Env {
+    /// Écrit des octets lus sur l'hôte : un texte remplace le contenu comme `wr`,
+    /// un binaire crée un nouveau fichier
+    pub fn put(&mut self, path: &Path, bytes: Vec<u8>, origin: Origin) -> Result<(), String> {
+        match split_content(bytes) {
+            (text, None) => self.wr(path, text, origin),
+            (_, Some(_)) if self.exists(path) => Err(format!("'{}' existe déjà", path.display())),
+            (_, Some(_)) if Self::is_protected(path) => Err(format!("Un fichier du coffre doit être du texte : '{}'", path.display())),
+            (_, data) => self.insert_entry(path, FsEntry::File(File {
+                name: String::new(),
+                content: String::new(),
+                system: false,
+                metadata: Metadata::now(),
+                history: Vec::new(),
+                data,
+            })),
+        }
+    }
+
+    /// Contenu brut d'un fichier, pour l'écrire sur l'hôte. Un fichier monté est relu
+    /// sur l'hôte tel quel, qu'il ait été ouvert ou non.
+    pub fn bytes(&self, path: &Path) -> Result<Vec<u8>, String> {
+        if let Some((mount, rel)) = self.mount_target(path) {
+            return HostDir::open(&mount)?.read_bytes(&rel);
+        }
+        match self.file(path).and_then(|file| file.data.clone()) {
+            Some(data) => Ok(data),
+            None => self.rd(path).map(String::into_bytes),
+        }
+    }
+
+    /// Aperçu d'une image ou dump hexadécimal paginé d'un fichier binaire
+    pub fn binary_ui(&mut self, ui: &mut egui::Ui, path: &Path, file: &File) {
+        let Some(data) = &file.data else {
+            return;
+        };
+        let kind = FileKind::detect(data);
+        ui.label(RichText::new(format!("{} · {} bytes", kind.label(), data.len())).size(12.0).color(Color32::GRAY));
+
+        if kind.is_image() {
+            let stale = self.image_preview.as_ref()
+                .is_none_or(|p| p.path != path || p.size != data.len() || p.modified != file.metadata.modified);
+            if stale {
+                self.image_preview = Some(ImagePreview {
+                    path: path.to_path_buf(),
+                    size: data.len(),
+                    modified: file.metadata.modified,
+                    texture: decode_image(ui.ctx(), path, data),
+                });
+            }
+            match self.image_preview.as_ref().map(|p| &p.texture) {
+                Some(Ok(texture)) => {
+                    ui.add(egui::Image::new(texture).max_width(ui.available_width()).shrink_to_fit());
+                    return;
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::LIGHT_RED, e);
+                }
+                None => {}
+            }
+        }
+
+        let pages = data.len().div_ceil(PAGE_SIZE).max(1);
+        self.hex_page = self.hex_page.min(pages - 1);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.hex_page > 0, egui::Button::new(RichText::new("<").size(12.0))).clicked() {
+                self.hex_page -= 1;
+            }
+            ui.label(RichText::new(format!("page {} / {}", self.hex_page + 1, pages)).size(12.0));
+            if ui.add_enabled(self.hex_page + 1 < pages, egui::Button::new(RichText::new(">").size(12.0))).clicked() {
+                self.hex_page += 1;
+            }
+        });
+        let start = self.hex_page * PAGE_SIZE;
+        let page = &data[start..data.len().min(start + PAGE_SIZE)];
+        for (i, bytes) in page.chunks(BYTES_PER_LINE).enumerate() {
+            ui.label(RichText::new(hex_line(start + i * BYTES_PER_LINE, bytes)).size(12.0).monospace());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::Vault;
+
+    #[test]
+    fn bytes_of_unopened_mounted_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let png = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0x00];
+        std::fs::write(tmp.path().join("image.png"), png).unwrap();
+        let mut env = Glob1Env::new(None, Vault::default());
+        env.mount(tmp.path(), Path::new("/m"), true).unwrap();
+
+        assert!(env.file(Path::new("/m/image.png")).unwrap().data.is_none());
+        assert_eq!(env.bytes(Path::new("/m/image.png")).unwrap(), png);
+        assert!(env.bytes(Path::new("/m/missing.png")).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+use egui::{OutputCommand, RichText};
+use zeroize::Zeroizing;
+
+/// Secret copié en attente d'effacement du presse-papiers
+#[derive(Clone)]
+struct PendingClear {
+    secret: Zeroizing<String>,
+    deadline: Instant,
+}
+
+/// Copie les secrets dans le presse-papiers et les en retire après `delay`,
+/// seulement si le presse-papiers contient toujours ce secret.
+#[derive(Clone)]
+pub struct SecretClipboard {
+    pub delay: Duration,
+    pending: Option<PendingClear>,
+}
+
+impl Default for SecretClipboard {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(20),
+            pending: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for SecretClipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretClipboard")
+            .field("delay", &self.delay)
+            .field("pending", &self.pending.is_some())
+            .finish()
+    }
+}
+
+impl SecretClipboard {
+    /// Bouton "copy" avec le compte à rebours si ce secret est celui en attente d'effacement
+    pub fn copy_button(&mut self, ui: &mut egui::Ui, secret: &str) {
+        let remaining = self.pending.as_ref()
+            .filter(|pending| pending.secret.as_str() == secret)
+            .map(|pending| pending.deadline.saturating_duration_since(Instant::now()));
+
+        let label = match remaining {
+            Some(remaining) => format!("copy ({}s)", remaining.as_secs() + 1),
+            None => "copy".to_string(),
+        };
+        if ui.button(RichText::new(label).size(14.0)).clicked() {
+            self.copy(ui.ctx(), secret);
+        }
+    }
+
+    pub fn copy(&mut self, ctx: &egui::Context, secret: &str) {
+        ctx.output_mut(|o| {
+            o.commands.push(OutputCommand::CopyText(secret.to_string()));
+        });
+        self.pending = Some(PendingClear {
+            secret: Zeroizing::new(secret.to_string()),
+            deadline: Instant::now() + self.delay,
+        });
+    }
+
+    /// À appeler à chaque frame : efface le secret une fois le délai écoulé
+    pub fn tick(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        if Instant::now() >= pending.deadline {
+            self.clear_now();
+        } else {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    pub fn clear_now(&mut self) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        match arboard::Clipboard::new() {
+            Ok(mut clipboard) => {
+                if clipboard.get_text().is_ok_and(|text| text == *pending.secret) {
+                    let _ = clipboard.clear();
+                }
+            }
+            Err(e) => eprintln!("Presse-papiers inaccessible : {}", e),
+        }
+    }
+}
@@ -0,0 +1,636 @@
+use std::path::Path;
+use std::process::exit;
+use std::sync::Arc;
+use crate::{NwtzUi, Page};
+use crate::audit::{self, AuditOptions};
+use crate::genpwd::{generate_password, PasswordOptions};
+use crate::fstree::Node;
+use crate::glob1env::Glob1Env;
+use crate::history::Origin;
+use crate::import_export::{self, Duplicates};
+use crate::metadata;
+use crate::search::{SearchHit, SearchMode, SearchQuery, MAX_RESULTS};
+use crate::storage;
+use crate::terminal::Terminal;
+use crate::trash::TRASH;
+use crate::vpath::VPath;
+
+pub struct CommandContext<'a> {
+    pub terminal: &'a mut Terminal,
+    pub nwtz_ui: &'a mut NwtzUi,
+    pub egui_ctx: &'a egui::Context,
+}
+
+pub trait Command: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn usage(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Sous-commandes affichées sous la commande dans `help` : (usage, description)
+    fn subcommands(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Page ouverte par la commande, affichée comme lien cliquable dans `help`
+    fn page(&self) -> Option<Page> {
+        None
+    }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page>;
+}
+
+#[derive(Clone)]
+pub struct CommandRegistry {
+    commands: Vec<Arc<dyn Command>>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = Self { commands: Vec::new() };
+        registry.register(HelpCommand);
+        registry.register(ClearCommand);
+        registry.register(ExitCommand);
+        registry.register(EchoCommand);
+        registry.register(TimeCommand);
+        registry.register(ParamsCommand);
+        registry.register(OpenCommand);
+        registry.register(MinimizeCommand);
+        registry.register(EnvCommand);
+        registry.register(LockCommand);
+        registry.register(GenpwdCommand);
+        registry.register(AuditCommand);
+        registry
+    }
+}
+
+impl CommandRegistry {
+    pub fn register(&mut self, command: impl Command + 'static) {
+        self.commands.push(Arc::new(command));
+    }
+
+    pub fn find(&self, name: &str) -> Option<Arc<dyn Command>> {
+        self.commands
+            .iter()
+            .find(|c| c.name() == name || c.aliases().contains(&name))
+            .cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Command>> {
+        self.commands.iter()
+    }
+}
+
+fn parse_page(name: &str) -> Option<Page> {
+    match name {
+        "terminal" | "term" => Some(Page::Terminal),
+        "settings" | "params" => Some(Page::Settings),
+        "env" | "glob1env" => Some(Page::Glob1Env),
+        _ => None,
+    }
+}
+
+pub struct HelpCommand;
+
+impl Command for HelpCommand {
+    fn name(&self) -> &'static str { "help" }
+    fn usage(&self) -> &'static str { "help" }
+    fn description(&self) -> &'static str { "Display this help message" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        let commands: Vec<Arc<dyn Command>> = ctx.nwtz_ui.commands.iter().cloned().collect();
+        let width = commands.iter().map(|c| c.usage().len()).max().unwrap_or(0);
+
+        ctx.terminal.add_response("Available commands:");
+        for command in commands {
+            let mut line = format!("  {:<width$} - {}", command.usage(), command.description());
+            if !command.aliases().is_empty() {
+                line.push_str(&format!(" (alias: {})", command.aliases().join(", ")));
+            }
+            ctx.terminal.add_response(&line);
+
+            for (usage, description) in command.subcommands() {
+                ctx.terminal.add_response(&format!("    {usage} - {description}"));
+            }
+            if let Some(page) = command.page() {
+                ctx.terminal.add_clickable(&format!("  ▶ Open {}", page.title()), Some(page));
+            }
+        }
+        None
+    }
+}
+
+pub struct ClearCommand;
+
+impl Command for ClearCommand {
+    fn name(&self) -> &'static str { "clear" }
+    fn usage(&self) -> &'static str { "clear" }
+    fn description(&self) -> &'static str { "Clear terminal history" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        ctx.terminal.clear();
+        None
+    }
+}
+
+pub struct ExitCommand;
+
+impl Command for ExitCommand {
+    fn name(&self) -> &'static str { "exit" }
+    fn usage(&self) -> &'static str { "exit" }
+    fn description(&self) -> &'static str { "Exit the application" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        ctx.terminal.add_response("Goodbye!");
+        exit(0)
+    }
+}
+
+pub struct EchoCommand;
+
+impl Command for EchoCommand {
+    fn name(&self) -> &'static str { "echo" }
+    fn usage(&self) -> &'static str { "echo <text>" }
+    fn description(&self) -> &'static str { "Echo text back to terminal" }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        ctx.terminal.add_response(&args.join(" "));
+        None
+    }
+}
+
+pub struct TimeCommand;
+
+impl Command for TimeCommand {
+    fn name(&self) -> &'static str { "time" }
+    fn usage(&self) -> &'static str { "time" }
+    fn description(&self) -> &'static str { "Display current date and time" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        let now = chrono::Local::now();
+        ctx.terminal.add_response(&format!("Current time: {}", now.format("%Y-%m-%d %H:%M:%S")));
+        None
+    }
+}
+
+pub struct ParamsCommand;
+
+impl Command for ParamsCommand {
+    fn name(&self) -> &'static str { "params" }
+    fn aliases(&self) -> &'static [&'static str] { &["settings"] }
+    fn usage(&self) -> &'static str { "params" }
+    fn description(&self) -> &'static str { "Go to parameters" }
+    fn page(&self) -> Option<Page> { Some(Page::Settings) }
+
+    fn run(&self, _args: &[&str], _ctx: &mut CommandContext) -> Option<Page> {
+        Some(Page::Settings)
+    }
+}
+
+pub struct OpenCommand;
+
+impl Command for OpenCommand {
+    fn name(&self) -> &'static str { "open" }
+    fn usage(&self) -> &'static str { "open <page>" }
+    fn description(&self) -> &'static str { "Open a page (terminal, settings, env)" }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        match args.first().and_then(|name| parse_page(name)) {
+            Some(page) => Some(page),
+            None => {
+                ctx.terminal.add_response(&format!("Usage: {}", self.usage()));
+                None
+            }
+        }
+    }
+}
+
+pub struct MinimizeCommand;
+
+impl Command for MinimizeCommand {
+    fn name(&self) -> &'static str { "minimize" }
+    fn usage(&self) -> &'static str { "minimize" }
+    fn description(&self) -> &'static str { "Minimize the application" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        ctx.terminal.add_response("Minimizing application...");
+        ctx.egui_ctx.memory_mut(|mem| {
+            mem.data.insert_persisted::<bool>("minimize_request".into(), true);
+        });
+        None
+    }
+}
+
+pub struct LockCommand;
+
+impl Command for LockCommand {
+    fn name(&self) -> &'static str { "lock" }
+    fn usage(&self) -> &'static str { "lock" }
+    fn description(&self) -> &'static str { "Lock the password vault" }
+
+    fn run(&self, _args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        ctx.nwtz_ui.glob1env.lock();
+        ctx.terminal.redact_secrets();
+        ctx.terminal.add_response("Vault locked");
+        None
+    }
+}
+
+pub struct GenpwdCommand;
+
+impl Command for GenpwdCommand {
+    fn name(&self) -> &'static str { "genpwd" }
+    fn usage(&self) -> &'static str { "genpwd [length]" }
+    fn description(&self) -> &'static str { "Generate a password" }
+
+    fn subcommands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("-L -U -D -S", "Without lowercase, uppercase, digits, symbols"),
+            ("-a", "Exclude ambiguous characters (Il1O0o)"),
+            ("-w <words> [--sep <sep>]", "Passphrase from the bundled wordlist"),
+        ]
+    }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        match PasswordOptions::from_args(args).and_then(|options| generate_password(&options)) {
+            Ok(password) => ctx.terminal.add_secret(&password),
+            Err(e) => ctx.terminal.add_error(&e),
+        }
+        None
+    }
+}
+
+pub struct AuditCommand;
+
+impl Command for AuditCommand {
+    fn name(&self) -> &'static str { "audit" }
+    fn usage(&self) -> &'static str { "audit [path]" }
+    fn description(&self) -> &'static str { "Check stored passwords for weak, reused or old secrets" }
+
+    fn subcommands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("--max-age <days>", "Flag secrets not updated since (default 365)"),
+            ("--breached <host path>", "SHA-1 breach list: HASH:COUNT file or directory of PREFIX files"),
+        ]
+    }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        let env = &ctx.nwtz_ui.glob1env;
+        if !env.vault.is_unlocked() {
+            ctx.terminal.add_error("Vault locked");
+            ctx.terminal.add_clickable("  ▶ Unlock", Some(Page::Glob1Env));
+            return None;
+        }
+
+        let findings = AuditOptions::from_args(args).and_then(|(options, rest)| {
+            let path = match rest.as_slice() {
+                [] => VPath::root(),
+                [path] => env.resolve(path),
+                _ => return Err(format!("Usage: {}", self.usage())),
+            };
+            let credentials = env.credentials(&path)?;
+            audit::audit(&credentials, &options).map(|findings| (credentials.len(), findings))
+        });
+
+        match findings {
+            Ok((total, findings)) => {
+                ctx.terminal.add_response(&format!("{} credentials checked, {} with issues", total, findings.len()));
+                for finding in findings {
+                    ctx.terminal.add_file_link(&format!("  ▶ {}", finding.label()), finding.file);
+                }
+            }
+            Err(e) => ctx.terminal.add_error(&e),
+        }
+        None
+    }
+}
+
+pub struct EnvCommand;
+
+impl Command for EnvCommand {
+    fn name(&self) -> &'static str { "env" }
+    fn usage(&self) -> &'static str { "env" }
+    fn description(&self) -> &'static str { "Go to environment" }
+    fn page(&self) -> Option<Page> { Some(Page::Glob1Env) }
+
+    fn subcommands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("pwd", "Print the current directory"),
+            ("ls [-a] [path]", "List a directory, hidden entries included with -a"),
+            ("cd [path]", "Change the current directory"),
+            ("tree [path]", "Print a directory tree"),
+            ("rd <path>", "Read a file"),
+            ("wr <path> <content>", "Write to a file"),
+            ("touch <path>", "Create an empty file"),
+            ("mkdir <path>", "Create a directory"),
+            ("rm [-r] <path>", "Move a file or directory to the trash (delete it if already there)"),
+            ("mv <from> <to>", "Move or rename an entry"),
+            ("cp <from> <to>", "Copy an entry and its content"),
+            ("stat <path>", "Show size, dates and flags of an entry"),
+            ("chmod <+|-><r|h|x> <path>", "Set or clear the read-only, hidden and executable flags"),
+            ("put <host file> <path>", "Copy a host file, text or binary, into the environment"),
+            ("get <path> <host file>", "Copy a file out to the host"),
+            ("mount [--ro] <host dir> <path>", "Mount a host directory (read-only with --ro)"),
+            ("umount <path>", "Unmount a host directory"),
+            ("mounts", "List mounted host directories"),
+            ("archive [--no-manifest] <dir> <host file>", "Export a directory to a .tar, .tar.gz or .zip archive, flags and dates in a manifest"),
+            ("extract [--keep-system] <host file> <dir>", "Import an archive into a directory, created if missing; system flags only with --keep-system"),
+            ("trash", "List trashed entries and where they came from"),
+            ("trash restore <name>", "Put a trashed entry back where it was"),
+            ("trash empty", "Permanently delete the trash content"),
+            ("undo", "Undo the last filesystem change (Ctrl+Z in the explorer)"),
+            ("find [-i] [-a] [--text|--regex] <pattern> [path]", "Find entries by name (glob by default)"),
+            ("grep [-i] [-a] [--glob|--regex] <pattern> [path]", "Find lines in files (plain text by default)"),
+            ("import [--dry-run] [--overwrite|--keep-duplicates] <host file> <file.pwd>", "Import a Bitwarden JSON or KeePass/Chromium CSV export"),
+            ("export [--json] <file.pwd|dir> <host file>", "Export credentials to CSV (or Bitwarden JSON)"),
+        ]
+    }
+
+    fn run(&self, args: &[&str], ctx: &mut CommandContext) -> Option<Page> {
+        let env = &mut ctx.nwtz_ui.glob1env;
+        let result = match args {
+            [] => return Some(Page::Glob1Env),
+            ["pwd"] => Ok(vec![env.current_path.display().to_string()]),
+            ["ls"] => env.ls().map(|entries| list_lines(&entries, false)),
+            ["ls", "-a"] => env.ls().map(|entries| list_lines(&entries, true)),
+            ["ls", "-a", path] => env.ls_path(&env.resolve(path)).map(|entries| list_lines(&entries, true)),
+            ["ls", path] => env.ls_path(&env.resolve(path)).map(|entries| list_lines(&entries, false)),
+            ["cd"] => env.cd(Path::new("/")).map(|_| Vec::new()),
+            ["cd", path] => {
+                let path = env.resolve(path);
+                env.cd(&path).map(|_| Vec::new())
+            }
+            ["tree"] => env.tree(&env.current_path),
+            ["tree", path] => env.tree(&env.resolve(path)),
+            ["rd", path] if Glob1Env::is_protected(&env.resolve(path)) && !env.vault.is_unlocked() => {
+                ctx.terminal.add_error("Vault locked");
+                ctx.terminal.add_clickable("  ▶ Unlock", Some(Page::Glob1Env));
+                return None;
+            }
+            ["rd", path] if Glob1Env::is_protected(&env.resolve(path)) => {
+                let path = env.resolve(path);
+                match env.rd_secret(&path) {
+                    Ok(content) => {
+                        env.mark_accessed(&path);
+                        content.lines().for_each(|line| ctx.terminal.add_secret(line));
+                    }
+                    Err(e) => ctx.terminal.add_error(&e),
+                }
+                return None;
+            }
+            ["rd", path] => {
+                let path = env.resolve(path);
+                let result = env.rd(&path).map(|content| content.lines().map(String::from).collect());
+                if result.is_ok() {
+                    env.mark_accessed(&path);
+                }
+                result
+            }
+            ["wr", path, content @ ..] if !content.is_empty() => {
+                let path = env.resolve(path);
+                env.wr(&path, content.join(" "), Origin::Terminal).map(|_| vec![format!("Wrote to `{}`", path.display())])
+            }
+            ["touch", path] => {
+                let path = env.resolve(path);
+                env.touch(&path).map(|_| Vec::new())
+            }
+            ["mkdir", path] => {
+                let path = env.resolve(path);
+                env.mkdir(&path).map(|_| Vec::new())
+            }
+            ["rm", path] => {
+                let path = env.resolve(path);
+                env.rm(&path, false).map(|_| Vec::new())
+            }
+            ["rm", "-r", path] => {
+                let path = env.resolve(path);
+                env.rm(&path, true).map(|_| Vec::new())
+            }
+            ["mv", from, to] => {
+                let (from, to) = (env.resolve(from), env.resolve(to));
+                env.mv(&from, &to).map(|_| Vec::new())
+            }
+            ["cp", from, to] => {
+                let (from, to) = (env.resolve(from), env.resolve(to));
+                env.cp(&from, &to).map(|_| Vec::new())
+            }
+            ["mount", rest @ ..] => match rest {
+                ["--ro", host, at] => mount(env, host, at, true),
+                [host, at] => mount(env, host, at, false),
+                _ => Err("Usage: env mount [--ro] <host dir> <path>".into()),
+            },
+            ["umount", path] => {
+                let path = env.resolve(path);
+                env.umount(&path).map(|_| Vec::new())
+            }
+            ["mounts"] => Ok(env.mounts().into_iter()
+                .map(|(at, mount)| format!("{} → {}{}", at.display(), mount.host.display(), if mount.readonly { " (read-only)" } else { "" }))
+                .collect()),
+            ["put", host, path] => {
+                let (host, path) = (import_export::expand_home(host), env.resolve(path));
+                std::fs::read(&host)
+                    .map_err(|e| format!("Lecture de '{}' impossible : {}", host.display(), e))
+                    .and_then(|bytes| env.put(&path, bytes, Origin::Terminal))
+                    .map(|_| vec![format!("Copied `{}` to `{}`", host.display(), path.display())])
+            }
+            ["get", path, _] if Glob1Env::is_protected(&env.resolve(path)) => {
+                Err("Vault files are exported with `env export`".into())
+            }
+            ["get", path, host] => {
+                let (path, host) = (env.resolve(path), import_export::expand_home(host));
+                env.bytes(&path)
+                    .and_then(|bytes| storage::write_atomic(&host, &bytes))
+                    .map(|_| vec![format!("Copied `{}` to `{}`", path.display(), host.display())])
+            }
+            ["archive", rest @ ..] => match rest {
+                ["--no-manifest", dir, host] => archive(env, dir, host, false),
+                [dir, host] => archive(env, dir, host, true),
+                _ => Err("Usage: env archive [--no-manifest] <dir> <host file>".into()),
+            },
+            ["extract", rest @ ..] => match rest {
+                ["--keep-system", host, dir] => extract(env, host, dir, true),
+                [host, dir] => extract(env, host, dir, false),
+                _ => Err("Usage: env extract [--keep-system] <host file> <dir>".into()),
+            },
+            ["trash"] => {
+                let entries = env.trashed();
+                if entries.is_empty() {
+                    Ok(vec!["Trash is empty".to_string()])
+                } else {
+                    for entry in entries {
+                        ctx.terminal.add_file_link(&format!("  ▶ {}", entry.label()), entry.path);
+                    }
+                    return None;
+                }
+            }
+            ["trash", "restore", name] => VPath::from(TRASH).join(name)
+                .and_then(|path| env.restore_trashed(&path))
+                .map(|to| vec![format!("Restored to `{}`", to.display())]),
+            ["trash", "empty"] => {
+                let count = env.empty_trash();
+                Ok(vec![format!("Deleted {} entries permanently", count)])
+            }
+            ["undo"] => env.undo().map(|done| vec![done]),
+            ["stat", path] => env.stat(&env.resolve(path)),
+            ["chmod", mode, path] => {
+                let path = env.resolve(path);
+                metadata::parse_mode(mode)
+                    .and_then(|(flags, value)| env.chmod(&path, &flags, value))
+                    .map(|_| Vec::new())
+            }
+            ["find" | "grep", rest @ ..] if !rest.is_empty() => {
+                match search(env, rest, args[0] == "grep") {
+                    Ok(hits) => {
+                        for hit in &hits {
+                            ctx.terminal.add_file_link(&format!("  ▶ {}", hit.label(Path::new("/"))), hit.path.clone());
+                        }
+                        let more = if hits.len() >= MAX_RESULTS { " (truncated)" } else { "" };
+                        ctx.terminal.add_response(&format!("{} results{}", hits.len(), more));
+                    }
+                    Err(e) => ctx.terminal.add_error(&e),
+                }
+                return None;
+            }
+            ["import" | "export", ..] if !env.vault.is_unlocked() => {
+                ctx.terminal.add_error("Vault locked");
+                ctx.terminal.add_clickable("  ▶ Unlock", Some(Page::Glob1Env));
+                return None;
+            }
+            ["import", rest @ ..] if rest.len() >= 2 => import(env, rest),
+            ["export", rest @ ..] if rest.len() >= 2 => export(env, rest),
+            _ => {
+                ctx.terminal.add_response(&format!("  {} - {}", self.usage(), self.description()));
+                for (usage, description) in self.subcommands() {
+                    ctx.terminal.add_response(&format!("    {usage} - {description}"));
+                }
+                return None;
+            }
+        };
+
+        match result {
+            Ok(lines) => lines.iter().for_each(|line| ctx.terminal.add_response(line)),
+            Err(e) => ctx.terminal.add_error(&e),
+        }
+        None
+    }
+}
+
+fn mount(env: &mut Glob1Env, host: &str, at: &str, readonly: bool) -> Result<Vec<String>, String> {
+    let at = env.resolve(at);
+    env.mount(&import_export::expand_home(host), &at, readonly)?;
+    Ok(vec![format!("Mounted `{}` on `{}`", host, at.display())])
+}
+
+fn archive(env: &Glob1Env, dir: &str, host: &str, manifest: bool) -> Result<Vec<String>, String> {
+    let host = import_export::expand_home(host);
+    env.export_archive(&env.resolve(dir), &host, manifest).map(|report| report.exported(&host))
+}
+
+fn extract(env: &mut Glob1Env, host: &str, dir: &str, keep_system: bool) -> Result<Vec<String>, String> {
+    let dir = env.resolve(dir);
+    env.import_archive(&import_export::expand_home(host), &dir, keep_system).map(|report| report.imported(&dir))
+}
+
+/// `[-i] [-a] [--text|--glob|--regex] <pattern> [path]` ; `content` pour `grep`
+fn search(env: &Glob1Env, args: &[&str], content: bool) -> Result<Vec<SearchHit>, String> {
+    let mut query = SearchQuery {
+        mode: if content { SearchMode::Substring } else { SearchMode::Glob },
+        case_sensitive: true,
+        content,
+        ..Default::default()
+    };
+    let mut rest = Vec::new();
+    for arg in args {
+        match *arg {
+            "-i" => query.case_sensitive = false,
+            "-a" => query.include_hidden = true,
+            "--text" => query.mode = SearchMode::Substring,
+            "--glob" => query.mode = SearchMode::Glob,
+            "--regex" => query.mode = SearchMode::Regex,
+            other => rest.push(other),
+        }
+    }
+
+    let scope = match rest.as_slice() {
+        [pattern] => {
+            query.pattern = pattern.to_string();
+            env.current_path.clone()
+        }
+        [pattern, path] => {
+            query.pattern = pattern.to_string();
+            env.resolve(path)
+        }
+        _ => return Err("Usage: env find|grep [options] <pattern> [path]".into()),
+    };
+    env.search(&scope, &query)
+}
+
+/// `[--dry-run] [--overwrite|--keep-duplicates] <host file> <file.pwd>`
+fn import(env: &mut Glob1Env, args: &[&str]) -> Result<Vec<String>, String> {
+    let (flags, paths) = args.split_at(args.len() - 2);
+    let mut dry_run = false;
+    let mut duplicates = Duplicates::Skip;
+    for flag in flags {
+        match *flag {
+            "--dry-run" => dry_run = true,
+            "--overwrite" => duplicates = Duplicates::Overwrite,
+            "--keep-duplicates" => duplicates = Duplicates::Keep,
+            other => return Err(format!("Unknown option: '{}'", other)),
+        }
+    }
+
+    let target = env.resolve(paths[1]);
+    if !Glob1Env::is_protected(&target) {
+        return Err(format!("'{}' is not a .pwd file", target.display()));
+    }
+    let incoming = import_export::read_host_file(&import_export::expand_home(paths[0]))?;
+    let existing = if env.exists(&target) { env.rd(&target)? } else { String::new() };
+
+    let (content, report) = import_export::merge(&existing, incoming, duplicates)?;
+    if !dry_run {
+        env.wr(&target, content, Origin::Terminal)?;
+    }
+    Ok(report.lines(dry_run))
+}
+
+/// `[--json] <file.pwd|dir> <host file>`
+fn export(env: &Glob1Env, args: &[&str]) -> Result<Vec<String>, String> {
+    let (json, paths) = match args {
+        ["--json", source, target] => (true, [*source, *target]),
+        [source, target] => (false, [*source, *target]),
+        _ => return Err("Usage: env export [--json] <file.pwd|dir> <host file>".into()),
+    };
+
+    let mut entries = Vec::new();
+    for file in env.vault_files(&env.resolve(paths[0]))? {
+        entries.extend(import_export::exported_entries(&env.rd(&file)?));
+    }
+    let data = if json {
+        import_export::export_json(&entries)
+    } else {
+        import_export::export_csv(&entries)?
+    };
+    let target = import_export::expand_home(paths[1]);
+    storage::write_private(&target, data.as_bytes())?;
+
+    Ok(vec![
+        format!("Exported {} credentials to `{}`", entries.len(), target.display()),
+        "Warning: this file contains your passwords in plain text".to_string(),
+    ])
+}
+
+fn list_lines(entries: &[&Node], all: bool) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| all || !entry.metadata().hidden)
+        .map(|entry| match entry {
+            Node::Directory(dir) => format!("📁 {}/", dir.name),
+            Node::File(file) => format!("📃 {}", file.name),
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+use egui::text_edit::TextEditState;
+use egui::{Color32, Id, Key, KeyboardShortcut, Modifiers, RichText};
+use zeroize::Zeroizing;
+
+const SAVE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+
+pub enum EditorAction {
+    None,
+    /// Contenu à écrire ; `system` si l'écriture d'un fichier système a été autorisée
+    Save { content: Zeroizing<String>, system: bool },
+    Close,
+}
+
+/// Édition d'un fichier du Glob1Env. Le tampon est effacé de la mémoire à la fermeture,
+/// les fichiers `.pwd` y étant déchiffrés. Le `TextEdit` d'egui garde ses propres copies
+/// (historique d'annulation) que `close` retire de sa mémoire, sans pouvoir les effacer.
+#[derive(Clone)]
+pub struct Editor {
+    pub path: PathBuf,
+    /// Id du `TextEdit`, pour retrouver son état dans la mémoire d'egui
+    id: Id,
+    /// Contexte du dernier affichage, pour `close`
+    ctx: Option<egui::Context>,
+    buffer: Zeroizing<String>,
+    original: Zeroizing<String>,
+    system: bool,
+    system_unlocked: bool,
+    /// Attribut lecture seule du fichier, levé avec `env chmod -r`
+    readonly: bool,
+    pub confirm_close: bool,
+    pub error: Option<String>,
+}
+
+impl std::fmt::Debug for Editor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Editor")
+            .field("path", &self.path)
+            .field("dirty", &self.is_dirty())
+            .field("system", &self.system)
+            .field("readonly", &self.readonly)
+            .finish()
+    }
+}
+
+impl Editor {
+    pub fn new(path: PathBuf, content: Zeroizing<String>, system: bool, readonly: bool) -> Self {
+        Self {
+            id: Id::new(("glob1env_editor", &path)),
+            ctx: None,
+            path,
+            buffer: content.clone(),
+            original: content,
+            system,
+            system_unlocked: false,
+            readonly,
+            confirm_close: false,
+            error: None,
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        *self.buffer != *self.original
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.readonly || (self.system && !self.system_unlocked)
+    }
+
+    /// Ferme l'éditeur en retirant l'état du `TextEdit` (curseur, annulations) de la mémoire d'egui
+    pub fn close(self) {
+        if let Some(ctx) = &self.ctx {
+            ctx.data_mut(|data| data.remove::<TextEditState>(self.id));
+        }
+    }
+
+    /// À appeler après une écriture réussie
+    pub fn saved(&mut self) {
+        self.original = self.buffer.clone();
+        self.error = None;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> EditorAction {
+        self.ctx.get_or_insert_with(|| ui.ctx().clone());
+        let mut action = EditorAction::None;
+        let save = |editor: &Self| EditorAction::Save { content: editor.buffer.clone(), system: editor.system_unlocked };
+
+        ui.horizontal(|ui| {
+            let can_save = self.is_dirty() && !self.is_read_only();
+            if ui.add_enabled(can_save, egui::Button::new(RichText::new("save").size(14.0))).on_hover_text("Ctrl+S").clicked() {
+                action = save(self);
+            }
+            if ui.add_enabled(self.is_dirty(), egui::Button::new(RichText::new("revert").size(14.0))).clicked() {
+                self.buffer = self.original.clone();
+            }
+            if ui.button(RichText::new("close").size(14.0)).clicked() {
+                if self.is_dirty() {
+                    self.confirm_close = true;
+                } else {
+                    action = EditorAction::Close;
+                }
+            }
+            if self.is_dirty() {
+                ui.label(RichText::new("● modified").size(12.0).color(Color32::YELLOW));
+            }
+        });
+
+        if self.readonly {
+            ui.colored_label(Color32::GRAY, RichText::new("Read-only file").size(12.0));
+        } else if self.system {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::CYAN, RichText::new("System file").size(12.0));
+                ui.checkbox(&mut self.system_unlocked, RichText::new("allow editing").size(12.0));
+            });
+        }
+
+        if self.confirm_close {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::YELLOW, "Unsaved changes:");
+                if !self.is_read_only() && ui.button("save").clicked() {
+                    action = save(self);
+                }
+                if ui.button("discard").clicked() {
+                    action = EditorAction::Close;
+                }
+                if ui.button("cancel").clicked() {
+                    self.confirm_close = false;
+                }
+            });
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::LIGHT_RED, error);
+        }
+        ui.separator();
+
+        let read_only = self.is_read_only();
+        egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+            ui.add_sized(
+                ui.available_size(),
+                egui::TextEdit::multiline(&mut *self.buffer)
+                    .id(self.id)
+                    .code_editor()
+                    .interactive(!read_only),
+            );
+        });
+
+        if ui.input_mut(|i| i.consume_shortcut(&SAVE_SHORTCUT)) && self.is_dirty() && !self.is_read_only() {
+            action = save(self);
+        }
+        action
+    }
+}
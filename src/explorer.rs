@@ -0,0 +1,564 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText};
+use crate::glob1env::Glob1Env;
+use crate::import_export::expand_home;
+use crate::metadata::{format_size, format_time, Flag};
+use crate::search::{SearchCache, SearchHit, SearchMode};
+use crate::trash::{in_trash, TRASH};
+
+/// Saisie en cours dans la barre d'outils de l'explorateur
+#[derive(Clone, Debug, Default)]
+pub enum ExplorerDialog {
+    #[default]
+    None,
+    Create { directory: bool, name: String },
+    Rename { path: PathBuf, name: String },
+    Delete { path: PathBuf },
+    ExportArchive { path: PathBuf, host: String },
+    ImportArchive { path: PathBuf, host: String, keep_system: bool },
+}
+
+#[derive(Clone, Debug)]
+pub enum ExplorerAction {
+    Create { directory: bool, name: String },
+    Rename { path: PathBuf, name: String },
+    Duplicate(PathBuf),
+    Delete(PathBuf),
+    Move { from: PathBuf, to: PathBuf },
+    Restore(PathBuf),
+    Undo,
+    ExportArchive { path: PathBuf, host: String },
+    ImportArchive { path: PathBuf, host: String, keep_system: bool },
+}
+
+/// Ligne de la liste, copiée depuis l'arborescence : l'affichage a besoin de `self` en mutable
+#[derive(Clone, Debug)]
+pub struct ExplorerRow {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    system: bool,
+    hidden: bool,
+    mount: bool,
+    size: usize,
+    modified: Option<DateTime<Utc>>,
+    flags: String,
+}
+
+/// Lignes du répertoire courant, recalculées quand l'arborescence ou l'affichage change ;
+/// la taille des sous-répertoires n'est ainsi pas recalculée à chaque image
+#[derive(Clone, Debug)]
+pub struct ExplorerCache {
+    pub path: PathBuf,
+    pub generation: u64,
+    pub show_hidden: bool,
+    pub details: bool,
+    pub rows: Result<Arc<[ExplorerRow]>, String>,
+}
+
+impl Glob1Env {
+    /// Boutons de création et saisie de la boîte de dialogue en cours, sous l'en-tête
+    pub fn explorer_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(RichText::new("+ file").size(12.0)).clicked() {
+                self.explorer_dialog = ExplorerDialog::Create { directory: false, name: String::new() };
+            }
+            if ui.button(RichText::new("+ folder").size(12.0)).clicked() {
+                self.explorer_dialog = ExplorerDialog::Create { directory: true, name: String::new() };
+            }
+            if ui.button(RichText::new("🗑 trash").size(12.0)).clicked() {
+                self.explorer_error = self.cd(Path::new(TRASH)).err().map(|_| "Trash is empty".to_string());
+            }
+            if ui.add_enabled(!self.undo_stack.is_empty(), egui::Button::new(RichText::new("undo").size(12.0)))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.apply(ExplorerAction::Undo);
+            }
+            if self.mount_target(&self.current_path).is_some() && ui.button(RichText::new("⟳").size(12.0)).on_hover_text("Reload from disk").clicked() {
+                let path = self.current_path.clone();
+                self.explorer_error = self.sync_path(&path).err();
+            }
+            ui.toggle_value(&mut self.explorer_details, RichText::new("details").size(12.0));
+            ui.toggle_value(&mut self.show_hidden, RichText::new("hidden").size(12.0));
+        });
+
+        let mut action = None;
+        let mut close = false;
+        match &mut self.explorer_dialog {
+            ExplorerDialog::None => {}
+            ExplorerDialog::Create { directory, name } => {
+                let hint = if *directory { "Folder name" } else { "File name" };
+                let (submit, cancel) = name_input(ui, name, hint, "create");
+                if submit {
+                    action = Some(ExplorerAction::Create { directory: *directory, name: name.clone() });
+                }
+                close = cancel;
+            }
+            ExplorerDialog::Rename { path, name } => {
+                let (submit, cancel) = name_input(ui, name, "New name", "rename");
+                if submit {
+                    action = Some(ExplorerAction::Rename { path: path.clone(), name: name.clone() });
+                }
+                close = cancel;
+            }
+            ExplorerDialog::Delete { path } => {
+                ui.horizontal(|ui| {
+                    let question = if in_trash(path) {
+                        format!("Delete '{}' permanently?", path.display())
+                    } else {
+                        format!("Move '{}' to the trash?", path.display())
+                    };
+                    ui.colored_label(Color32::YELLOW, question);
+                    if ui.button("delete").clicked() {
+                        action = Some(ExplorerAction::Delete(path.clone()));
+                    }
+                    close = ui.button("cancel").clicked();
+                });
+            }
+            ExplorerDialog::ExportArchive { path, host } => {
+                let (submit, cancel) = name_input(ui, host, "Host file (.tar, .tar.gz, .zip)", "export");
+                if submit {
+                    action = Some(ExplorerAction::ExportArchive { path: path.clone(), host: host.clone() });
+                }
+                close = cancel;
+            }
+            ExplorerDialog::ImportArchive { path, host, keep_system } => {
+                let (submit, cancel) = name_input(ui, host, "Archive on the host", "import");
+                ui.checkbox(keep_system, RichText::new("Keep system flags from the manifest").size(12.0));
+                if submit {
+                    action = Some(ExplorerAction::ImportArchive { path: path.clone(), host: host.clone(), keep_system: *keep_system });
+                }
+                close = cancel;
+            }
+        }
+
+        if close {
+            self.explorer_dialog = ExplorerDialog::None;
+            self.explorer_error = None;
+        }
+        if let Some(action) = action {
+            self.apply(action);
+        }
+        if let Some(error) = &self.explorer_error {
+            ui.colored_label(Color32::LIGHT_RED, RichText::new(error).size(12.0));
+        } else if let Some(notice) = &self.explorer_notice {
+            ui.colored_label(Color32::GRAY, RichText::new(notice).size(12.0));
+        }
+    }
+
+    /// Champ de recherche sous le répertoire courant, avec ses options
+    pub fn search_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.search.pattern).hint_text("Search").desired_width(ui.available_width() / 2.0));
+            egui::ComboBox::from_id_salt("search_mode")
+                .selected_text(self.search.mode.label())
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for mode in SearchMode::ALL {
+                        ui.selectable_value(&mut self.search.mode, mode, mode.label());
+                    }
+                });
+            ui.toggle_value(&mut self.search.case_sensitive, RichText::new("Aa").size(12.0)).on_hover_text("Match case");
+            ui.toggle_value(&mut self.search.content, RichText::new("in files").size(12.0)).on_hover_text("Search file contents");
+            if !self.search.pattern.is_empty() && ui.button(RichText::new("x").size(12.0)).clicked() {
+                self.search.pattern.clear();
+            }
+        });
+    }
+
+    /// Contenu du répertoire courant, en colonnes si la vue détaillée est active,
+    /// ou résultats de la recherche en cours
+    pub fn explorer_list(&mut self, ui: &mut egui::Ui) {
+        if !self.search.pattern.is_empty() {
+            self.search_results(ui);
+            return;
+        }
+        let rows = match &self.explorer_cache {
+            Some(cache) if cache.path == *self.current_path
+                && cache.generation == self.tree.generation()
+                && cache.show_hidden == self.show_hidden
+                && cache.details == self.explorer_details => cache.rows.clone(),
+            _ => {
+                let rows = self.explorer_rows().map(Arc::from);
+                self.explorer_cache = Some(ExplorerCache {
+                    path: self.current_path.to_path_buf(),
+                    generation: self.tree.generation(),
+                    show_hidden: self.show_hidden,
+                    details: self.explorer_details,
+                    rows: rows.clone(),
+                });
+                rows
+            }
+        };
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(e) => {
+                ui.label("Impossible de lister le répertoire.");
+                eprintln!("{}", e);
+                return;
+            }
+        };
+
+        if !self.explorer_details {
+            for row in rows.iter() {
+                self.explorer_row(ui, row);
+            }
+            return;
+        }
+        egui::Grid::new("explorer_details").striped(true).show(ui, |ui| {
+            for label in ["Name", "Size", "Modified", "Flags"] {
+                ui.label(RichText::new(label).size(12.0).strong());
+            }
+            ui.end_row();
+            for row in rows.iter() {
+                self.explorer_row(ui, row);
+                ui.label(RichText::new(format_size(row.size)).size(12.0).color(Color32::GRAY));
+                ui.label(RichText::new(format_time(row.modified)).size(12.0).color(Color32::GRAY));
+                ui.label(RichText::new(&row.flags).size(12.0).monospace().color(Color32::GRAY));
+                ui.end_row();
+            }
+        });
+    }
+
+    fn search_results(&mut self, ui: &mut egui::Ui) {
+        self.search.include_hidden = self.show_hidden;
+        let hits = match &self.search_cache {
+            Some(cache) if cache.query == self.search
+                && cache.scope == *self.current_path
+                && cache.generation == self.tree.generation() => cache.hits.clone(),
+            _ => {
+                let hits = self.search(&self.current_path, &self.search);
+                self.search_cache = Some(SearchCache {
+                    query: self.search.clone(),
+                    scope: self.current_path.to_path_buf(),
+                    generation: self.tree.generation(),
+                    hits: hits.clone(),
+                });
+                hits
+            }
+        };
+
+        match hits {
+            Ok(hits) if hits.is_empty() => {
+                ui.label(RichText::new("No results").size(12.0).color(Color32::GRAY));
+            }
+            Ok(hits) => {
+                for hit in hits {
+                    self.search_hit(ui, &hit);
+                }
+            }
+            Err(e) => {
+                ui.colored_label(Color32::LIGHT_RED, RichText::new(e).size(12.0));
+            }
+        }
+    }
+
+    fn search_hit(&mut self, ui: &mut egui::Ui, hit: &SearchHit) {
+        let is_dir = self.is_dir(&hit.path);
+        let icon = if is_dir { "📁" } else { "📃" };
+        let selected = self.selected_file.as_ref() == Some(&hit.path);
+        let label = format!("{} {}", icon, hit.label(&self.current_path));
+        if ui.selectable_label(selected, RichText::new(label).size(13.0)).clicked() {
+            if is_dir {
+                // Comme `cd`, un répertoire monté est relu sur l'hôte avant d'être affiché
+                match self.cd(&hit.path) {
+                    Ok(_) => self.search.pattern.clear(),
+                    Err(e) => self.explorer_error = Some(e),
+                }
+            } else {
+                self.select(hit.path.clone());
+            }
+        }
+    }
+
+    fn explorer_rows(&self) -> Result<Vec<ExplorerRow>, String> {
+        let dir = self.lookup(&self.current_path)
+            .ok_or_else(|| format!("Répertoire introuvable : '{}'", self.current_path.display()))?;
+        Ok(self.tree.children(dir).iter()
+            .map(|&id| (id, self.tree.node(id)))
+            .filter(|(_, node)| self.show_hidden || !node.metadata().hidden)
+            .map(|(id, node)| ExplorerRow {
+                name: node.name().to_string(),
+                path: self.tree.path(id),
+                is_dir: node.is_dir(),
+                system: node.is_system(),
+                hidden: node.metadata().hidden,
+                mount: node.as_dir().is_some_and(|d| d.mount.is_some()),
+                size: if self.explorer_details { self.size(id) } else { 0 },
+                modified: node.metadata().modified,
+                flags: node.metadata().flags(node.is_system()),
+            })
+            .collect())
+    }
+
+    fn explorer_row(&mut self, ui: &mut egui::Ui, row: &ExplorerRow) {
+        let color = if row.system {
+            Color32::CYAN
+        } else if row.hidden {
+            Color32::GRAY
+        } else {
+            Color32::WHITE
+        };
+        let icon = match (row.mount, row.is_dir) {
+            (true, _) => "🔗",
+            (false, true) => "📁",
+            (false, false) => "📃",
+        };
+        let selected = !row.is_dir && self.selected_file.as_ref() == Some(&row.path);
+        let response = ui.selectable_label(selected, RichText::new(format!("{} {}", icon, row.name)).color(color).size(14.0))
+            .interact(egui::Sense::drag());
+        if response.clicked() {
+            if row.is_dir {
+                self.push(&row.name);
+            } else {
+                self.select(row.path.clone());
+            }
+        }
+        if row.is_dir {
+            self.drop_target(&response, &row.path);
+        }
+        if !row.system {
+            response.dnd_set_drag_payload(row.path.clone());
+        }
+        self.entry_menu(&response, &row.path, row.system);
+    }
+
+    /// Menu contextuel d'une entrée de la liste ; les entrées système ne peuvent être que dupliquées,
+    /// celles en lecture seule dupliquées ou changées d'attributs
+    pub fn entry_menu(&mut self, response: &egui::Response, path: &Path, system: bool) {
+        response.context_menu(|ui| {
+            let Some(id) = self.lookup(path) else {
+                return;
+            };
+            let locked = system || self.tree.contains_readonly(id);
+            if ui.add_enabled(!locked, egui::Button::new("Rename")).clicked() {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                self.explorer_dialog = ExplorerDialog::Rename { path: path.to_path_buf(), name };
+                self.explorer_error = None;
+                ui.close_menu();
+            }
+            if in_trash(path) && ui.button("Restore").clicked() {
+                self.apply(ExplorerAction::Restore(path.to_path_buf()));
+                ui.close_menu();
+            }
+            if ui.button("Duplicate").clicked() {
+                self.apply(ExplorerAction::Duplicate(path.to_path_buf()));
+                ui.close_menu();
+            }
+            if self.tree.node(id).is_dir() {
+                if ui.button("Export archive…").clicked() {
+                    self.explorer_dialog = ExplorerDialog::ExportArchive { path: path.to_path_buf(), host: String::new() };
+                    self.explorer_error = None;
+                    ui.close_menu();
+                }
+                if ui.add_enabled(!locked, egui::Button::new("Import archive…")).clicked() {
+                    self.explorer_dialog = ExplorerDialog::ImportArchive { path: path.to_path_buf(), host: String::new(), keep_system: false };
+                    self.explorer_error = None;
+                    ui.close_menu();
+                }
+            }
+            if ui.add_enabled(!locked, egui::Button::new("Delete")).clicked() {
+                self.explorer_dialog = ExplorerDialog::Delete { path: path.to_path_buf() };
+                self.explorer_error = None;
+                ui.close_menu();
+            }
+            if system {
+                return;
+            }
+            ui.separator();
+            for flag in Flag::ALL {
+                let mut value = self.tree.node(id).metadata().flag(flag);
+                if ui.checkbox(&mut value, flag.label()).changed() {
+                    self.explorer_error = self.chmod(path, &[flag], value).err();
+                }
+            }
+        });
+    }
+
+    /// Dépôt d'une entrée glissée sur `response` : elle est déplacée dans `directory`
+    pub fn drop_target(&mut self, response: &egui::Response, directory: &Path) {
+        if let Some(from) = response.dnd_release_payload::<PathBuf>() {
+            self.apply(ExplorerAction::Move { from: (*from).clone(), to: directory.to_path_buf() });
+        }
+    }
+
+    pub fn apply(&mut self, action: ExplorerAction) {
+        self.explorer_notice = None;
+        let result = match action {
+            ExplorerAction::Create { directory, name } => {
+                let name = name.trim();
+                if name.is_empty() || name.contains('/') {
+                    Err(format!("Nom invalide : '{}'", name))
+                } else {
+                    let path = Self::join_fn(&self.current_path, name);
+                    if directory {
+                        self.mkdir(&path)
+                    } else if self.exists(&path) {
+                        Err(format!("'{}' existe déjà", path.display()))
+                    } else {
+                        self.touch(&path).map(|_| self.select(path))
+                    }
+                }
+            }
+            ExplorerAction::Rename { path, name } => self.rename(&path, &name).map(|to| self.moved(&path, Some(&to))),
+            ExplorerAction::Duplicate(path) => self.duplicate(&path).map(|_| ()),
+            ExplorerAction::Delete(path) => {
+                let recursive = self.is_dir(&path);
+                self.rm(&path, recursive)
+            }
+            ExplorerAction::Move { from, to } => {
+                let name = from.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                let destination = Self::join_fn(&to, &name);
+                if from.parent() == Some(to.as_path()) {
+                    Ok(())
+                } else {
+                    self.mv(&from, &to).map(|_| self.moved(&from, Some(&destination)))
+                }
+            }
+            ExplorerAction::Restore(path) => self.restore_trashed(&path)
+                .map(|to| self.explorer_notice = Some(format!("Restored to '{}'", to.display()))),
+            ExplorerAction::Undo => self.undo().map(|done| self.explorer_notice = Some(done)),
+            ExplorerAction::ExportArchive { path, host } => {
+                let host = expand_home(host.trim());
+                self.export_archive(&path, &host, true)
+                    .map(|report| self.explorer_notice = Some(report.exported(&host).join("\n")))
+            }
+            ExplorerAction::ImportArchive { path, host, keep_system } => self.import_archive(&expand_home(host.trim()), &path, keep_system)
+                .map(|report| self.explorer_notice = Some(report.imported(&path).join("\n"))),
+        };
+
+        match result {
+            Ok(()) => {
+                self.explorer_dialog = ExplorerDialog::None;
+                self.explorer_error = None;
+            }
+            Err(e) => self.explorer_error = Some(e),
+        }
+    }
+
+    /// Met à jour la sélection et l'éditeur après le déplacement ou la suppression de `from`
+    pub fn moved(&mut self, from: &Path, to: Option<&Path>) {
+        let relocate = |path: &Path| to.map(|to| match path.strip_prefix(from) {
+            Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+            _ => to.to_path_buf(),
+        });
+
+        if let Some(selected) = self.selected_file.clone()
+            && selected.starts_with(from)
+        {
+            self.selected_file = relocate(&selected);
+        }
+        if let Some(editor) = &self.editor
+            && editor.path.starts_with(from)
+        {
+            match relocate(&editor.path) {
+                Some(path) => self.editor.as_mut().unwrap().path = path,
+                None => self.close_editor(),
+            }
+        }
+        if self.current_path.starts_with(from) {
+            self.current_path = relocate(&self.current_path)
+                .unwrap_or_else(|| from.parent().unwrap_or(Path::new("/")).to_path_buf())
+                .into();
+        }
+    }
+}
+
+/// Champ de nom validé par Entrée ou le bouton ; renvoie (valider, annuler)
+fn name_input(ui: &mut egui::Ui, name: &mut String, hint: &str, submit_label: &str) -> (bool, bool) {
+    ui.horizontal(|ui| {
+        let response = ui.add(egui::TextEdit::singleline(name).hint_text(hint).desired_width(ui.available_width() / 2.0));
+        let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let submit = ui.button(submit_label).clicked() || entered;
+        let cancel = ui.button("cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape));
+        (submit, cancel)
+    })
+    .inner
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::Page;
+    use crate::fstree::FsTree;
+    use crate::glob1env::{Directory, File, FsEntry, Glob1Env};
+    use crate::metadata::Metadata;
+    use crate::vault::Vault;
+
+    const FILES_PER_DIR: usize = 1_000;
+
+    /// `entries` fichiers répartis en répertoires de `FILES_PER_DIR`,
+    /// le premier répertoire ouvert en vue détaillée avec un fichier affiché
+    fn env_with(entries: usize) -> Glob1Env {
+        let dirs = (0..entries / FILES_PER_DIR)
+            .map(|d| FsEntry::Directory(Directory {
+                name: format!("d{d}"),
+                content: (0..FILES_PER_DIR)
+                    .map(|f| FsEntry::File(File {
+                        name: format!("f{f}.txt"),
+                        content: format!("file {f} of directory {d}"),
+                        system: false,
+                        metadata: Metadata::now(),
+                        history: Vec::new(),
+                        data: None,
+                    }))
+                    .collect(),
+                system: false,
+                metadata: Metadata::now(),
+                mount: None,
+            }))
+            .collect();
+
+        let mut vault = Vault::default();
+        vault.create("bench").unwrap();
+        // Sans chemin de sauvegarde : le test ne lit ni n'écrit rien sur le disque
+        let mut env = Glob1Env::new(None, vault);
+        env.tree = FsTree::from_entries(vec![FsEntry::Directory(Directory {
+            name: "bench".into(),
+            content: dirs,
+            system: false,
+            metadata: Metadata::now(),
+            mount: None,
+        })]);
+        env.explorer_details = true;
+        env.current_path = "/bench/d0".into();
+        env.select("/bench/d0/f0.txt".into());
+        env
+    }
+
+    fn frame(ctx: &egui::Context, env: &mut Glob1Env) {
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            env.poll_changes(ctx);
+            egui::CentralPanel::default().show(ctx, |ui| env.ui(ui, &mut Page::Glob1Env));
+        });
+    }
+
+    /// Coût moyen d'une image de l'explorateur selon la taille de l'arborescence :
+    /// `cargo test --release frame_cost -- --ignored`
+    #[test]
+    #[ignore]
+    fn frame_cost_stays_flat() {
+        const FRAMES: u32 = 200;
+
+        let mut costs = Vec::new();
+        for entries in [1_000, 10_000, 100_000] {
+            let mut env = env_with(entries);
+            let ctx = egui::Context::default();
+            // Les premières images remplissent les caches
+            for _ in 0..5 {
+                frame(&ctx, &mut env);
+            }
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                frame(&ctx, &mut env);
+            }
+            costs.push((entries, start.elapsed() / FRAMES));
+        }
+
+        let (small, large) = (costs[0].1, costs[costs.len() - 1].1);
+        assert!(large < small * 2 + Duration::from_micros(500), "cost per frame by number of entries: {costs:?}");
+    }
+}
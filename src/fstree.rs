@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::glob1env::{Directory, File, FsEntry};
+use crate::metadata::Metadata;
+use crate::mount::Mount;
+
+pub type NodeId = usize;
+
+/// Le répertoire racine, toujours présent
+pub const ROOT: NodeId = 0;
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    File(File),
+    Directory(DirNode),
+}
+
+#[derive(Clone, Debug)]
+pub struct DirNode {
+    pub name: String,
+    pub system: bool,
+    pub metadata: Metadata,
+    pub mount: Option<Mount>,
+    /// Enfants dans l'ordre d'insertion, pour l'affichage
+    children: Vec<NodeId>,
+    /// Enfants par nom, pour les recherches
+    index: HashMap<String, NodeId>,
+}
+
+#[derive(Clone, Debug)]
+struct Slot {
+    parent: Option<NodeId>,
+    node: Node,
+}
+
+/// Arborescence du Glob1Env stockée dans une arène : les recherches coûtent O(profondeur)
+/// et les listings sont empruntés, sans cloner le contenu des fichiers.
+/// `FsEntry` reste le format de sauvegarde.
+#[derive(Clone, Debug)]
+pub struct FsTree {
+    slots: Vec<Option<Slot>>,
+    free: Vec<NodeId>,
+    /// Incrémenté à chaque accès mutable, pour invalider les caches
+    generation: u64,
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self {
+            slots: vec![Some(Slot { parent: None, node: Node::Directory(DirNode::new(String::new(), false, Metadata::default(), None)) })],
+            free: Vec::new(),
+            generation: 0,
+        }
+    }
+}
+
+impl DirNode {
+    fn new(name: String, system: bool, metadata: Metadata, mount: Option<Mount>) -> Self {
+        Self { name, system, metadata, mount, children: Vec::new(), index: HashMap::new() }
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn child(&self, name: &str) -> Option<NodeId> {
+        self.index.get(name).copied()
+    }
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::File(f) => &f.name,
+            Node::Directory(d) => &d.name,
+        }
+    }
+
+    fn set_name(&mut self, name: &str) {
+        match self {
+            Node::File(f) => f.name = name.to_string(),
+            Node::Directory(d) => d.name = name.to_string(),
+        }
+    }
+
+    pub fn is_system(&self) -> bool {
+        match self {
+            Node::File(f) => f.system,
+            Node::Directory(d) => d.system,
+        }
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        match self {
+            Node::File(f) => &f.metadata,
+            Node::Directory(d) => &d.metadata,
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        match self {
+            Node::File(f) => &mut f.metadata,
+            Node::Directory(d) => &mut d.metadata,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Node::Directory(_))
+    }
+
+    pub fn as_file(&self) -> Option<&File> {
+        match self {
+            Node::File(f) => Some(f),
+            Node::Directory(_) => None,
+        }
+    }
+
+    pub fn as_dir(&self) -> Option<&DirNode> {
+        match self {
+            Node::Directory(d) => Some(d),
+            Node::File(_) => None,
+        }
+    }
+}
+
+impl FsTree {
+    pub fn from_entries(entries: Vec<FsEntry>) -> Self {
+        let mut tree = Self::default();
+        for entry in entries {
+            // Les doublons d'un ancien fichier de sauvegarde sont ignorés
+            let _ = tree.insert(ROOT, entry);
+        }
+        tree
+    }
+
+    /// Contenu de la racine au format de sauvegarde
+    pub fn to_entries(&self) -> Vec<FsEntry> {
+        self.children(ROOT).iter().map(|&id| self.entry(id)).collect()
+    }
+
+    /// Copie du sous-arbre `id` ; un point de montage est copié sans le contenu lu sur l'hôte
+    pub fn entry(&self, id: NodeId) -> FsEntry {
+        match self.node(id) {
+            Node::File(f) => FsEntry::File(f.clone()),
+            Node::Directory(d) => FsEntry::Directory(Directory {
+                name: d.name.clone(),
+                content: match d.mount {
+                    Some(_) => Vec::new(),
+                    None => d.children.iter().map(|&child| self.entry(child)).collect(),
+                },
+                system: d.system,
+                metadata: d.metadata.clone(),
+                mount: d.mount.clone(),
+            }),
+        }
+    }
+
+    fn slot(&self, id: NodeId) -> &Slot {
+        self.slots[id].as_ref().expect("NodeId d'un nœud supprimé")
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.slot(id).node
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.generation += 1;
+        &mut self.slots[id].as_mut().expect("NodeId d'un nœud supprimé").node
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).parent
+    }
+
+    /// Enfants d'un répertoire, vide pour un fichier
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).as_dir().map(DirNode::children).unwrap_or_default()
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.node(dir).as_dir()?.child(name)
+    }
+
+    pub fn lookup(&self, parts: &[&str]) -> Option<NodeId> {
+        parts.iter().try_fold(ROOT, |id, name| self.node(id).as_dir()?.child(name))
+    }
+
+    /// Chemin absolu du nœud
+    pub fn path(&self, id: NodeId) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            names.push(self.node(current).name());
+            current = parent;
+        }
+        let mut path = PathBuf::from("/");
+        path.extend(names.into_iter().rev());
+        path
+    }
+
+    /// `id` et tous ses descendants, en profondeur d'abord
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut out = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            out.push(id);
+            stack.extend(self.children(id).iter().rev());
+        }
+        out
+    }
+
+    pub fn contains_system(&self, id: NodeId) -> bool {
+        self.descendants(id).into_iter().any(|id| self.node(id).is_system())
+    }
+
+    pub fn contains_readonly(&self, id: NodeId) -> bool {
+        self.descendants(id).into_iter().any(|id| self.node(id).metadata().readonly)
+    }
+
+    fn alloc(&mut self, slot: Slot) -> NodeId {
+        match self.free.pop() {
+            Some(id) => {
+                self.slots[id] = Some(slot);
+                id
+            }
+            None => {
+                self.slots.push(Some(slot));
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    fn attach(&mut self, parent: NodeId, id: NodeId) -> Result<(), String> {
+        let name = self.node(id).name().to_string();
+        let Node::Directory(dir) = self.node_mut(parent) else {
+            return Err(format!("'{}' n'est pas un répertoire", dir_name(self, parent)));
+        };
+        if dir.index.contains_key(&name) {
+            return Err(format!("'{}' existe déjà", name));
+        }
+        dir.index.insert(name, id);
+        dir.children.push(id);
+        self.slots[id].as_mut().unwrap().parent = Some(parent);
+        Ok(())
+    }
+
+    fn detach(&mut self, id: NodeId) {
+        let Some(parent) = self.parent(id) else {
+            return;
+        };
+        let name = self.node(id).name().to_string();
+        if let Node::Directory(dir) = self.node_mut(parent) {
+            dir.index.remove(&name);
+            dir.children.retain(|&child| child != id);
+        }
+        self.slots[id].as_mut().unwrap().parent = None;
+    }
+
+    /// Insère `entry` et son contenu dans le répertoire `parent`
+    pub fn insert(&mut self, parent: NodeId, entry: FsEntry) -> Result<NodeId, String> {
+        self.generation += 1;
+        let (node, content) = match entry {
+            FsEntry::File(f) => (Node::File(f), Vec::new()),
+            FsEntry::Directory(d) => (Node::Directory(DirNode::new(d.name, d.system, d.metadata, d.mount)), d.content),
+        };
+        let id = self.alloc(Slot { parent: None, node });
+        if let Err(e) = self.attach(parent, id) {
+            self.slots[id] = None;
+            self.free.push(id);
+            return Err(e);
+        }
+        for child in content {
+            // Un doublon dans le contenu est ignoré, comme dans `from_entries`
+            let _ = self.insert(id, child);
+        }
+        Ok(id)
+    }
+
+    /// Retire le sous-arbre `id` et le renvoie
+    pub fn remove(&mut self, id: NodeId) -> FsEntry {
+        let entry = self.entry(id);
+        self.generation += 1;
+        self.detach(id);
+        for id in self.descendants(id) {
+            self.slots[id] = None;
+            self.free.push(id);
+        }
+        entry
+    }
+
+    /// Déplace `id` dans `parent` sous le nom `name`, sans copier son contenu
+    pub fn relocate(&mut self, id: NodeId, parent: NodeId, name: &str) -> Result<(), String> {
+        let old_parent = self.parent(id).ok_or("Impossible de déplacer la racine")?;
+        let old_name = self.node(id).name().to_string();
+
+        self.generation += 1;
+        self.detach(id);
+        self.node_mut(id).set_name(name);
+        if let Err(e) = self.attach(parent, id) {
+            self.node_mut(id).set_name(&old_name);
+            self.attach(old_parent, id)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+fn dir_name(tree: &FsTree, id: NodeId) -> String {
+    tree.path(id).display().to_string()
+}
@@ -0,0 +1,170 @@
+use rand::Rng;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!#$%&*+-.:=?@^_~";
+/// Caractères faciles à confondre à l'écran ou à la dictée
+const AMBIGUOUS: &str = "Il1O0o";
+
+const WORDLIST: &str = include_str!("../assets/wordlist.txt");
+
+/// Bornes des options, pour qu'une faute de frappe ne remplisse pas la mémoire
+pub const MAX_LENGTH: usize = 1024;
+pub const MAX_WORDS: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct PasswordOptions {
+    pub length: usize,
+    pub lower: bool,
+    pub upper: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    /// Si défini, génère une phrase de passe de ce nombre de mots au lieu de caractères
+    pub words: Option<usize>,
+    pub separator: String,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lower: true,
+            upper: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            words: None,
+            separator: "-".to_string(),
+        }
+    }
+}
+
+impl PasswordOptions {
+    /// `[length] [-L] [-U] [-D] [-S] [-a] [-w <words>] [--sep <separator>]`
+    pub fn from_args(args: &[&str]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match *arg {
+                "-L" => options.lower = false,
+                "-U" => options.upper = false,
+                "-D" => options.digits = false,
+                "-S" => options.symbols = false,
+                "-a" => options.exclude_ambiguous = true,
+                "-w" => {
+                    let words = args.next().ok_or("-w expects a number of words")?;
+                    options.words = Some(words.parse().map_err(|_| format!("Invalid number of words: '{}'", words))?);
+                }
+                "--sep" => {
+                    options.separator = args.next().ok_or("--sep expects a separator")?.to_string();
+                }
+                length => {
+                    options.length = length.parse().map_err(|_| format!("Unknown option: '{}'", length))?;
+                }
+            }
+        }
+
+        options.check_bounds()?;
+        Ok(options)
+    }
+
+    fn check_bounds(&self) -> Result<(), String> {
+        if self.length > MAX_LENGTH {
+            return Err(format!("Length must be at most {}", MAX_LENGTH));
+        }
+        if self.words.is_some_and(|words| words > MAX_WORDS) {
+            return Err(format!("A passphrase has at most {} words", MAX_WORDS));
+        }
+        Ok(())
+    }
+}
+
+pub fn generate_password(options: &PasswordOptions) -> Result<String, String> {
+    options.check_bounds()?;
+    if let Some(words) = options.words {
+        return generate_passphrase(words, &options.separator);
+    }
+
+    let classes: Vec<Vec<char>> = [
+        (options.lower, LOWER),
+        (options.upper, UPPER),
+        (options.digits, DIGITS),
+        (options.symbols, SYMBOLS),
+    ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, set)| set.chars().filter(|c| !options.exclude_ambiguous || !AMBIGUOUS.contains(*c)).collect())
+        .collect();
+
+    if classes.is_empty() {
+        return Err("At least one character class is required".into());
+    }
+    if options.length < classes.len() {
+        return Err(format!("Length must be at least {}", classes.len()));
+    }
+
+    // Un caractère de chaque classe demandée, le reste tiré dans l'ensemble, puis mélangé
+    let all: Vec<char> = classes.iter().flatten().copied().collect();
+    let mut password: Vec<char> = classes.iter().map(|class| pick(class)).collect();
+    while password.len() < options.length {
+        password.push(pick(&all));
+    }
+    password.shuffle(&mut OsRng);
+
+    Ok(password.into_iter().collect())
+}
+
+pub fn generate_passphrase(words: usize, separator: &str) -> Result<String, String> {
+    if words == 0 {
+        return Err("A passphrase needs at least one word".into());
+    }
+    if words > MAX_WORDS {
+        return Err(format!("A passphrase has at most {} words", MAX_WORDS));
+    }
+    let list: Vec<&str> = WORDLIST.lines().filter(|w| !w.is_empty()).collect();
+    let chosen: Vec<&str> = (0..words).map(|_| pick(&list)).collect();
+    Ok(chosen.join(separator))
+}
+
+fn pick<T: Copy>(items: &[T]) -> T {
+    items[OsRng.gen_range(0..items.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_and_words_are_bounded() {
+        assert_eq!(PasswordOptions::from_args(&["1024"]).unwrap().length, 1024);
+        assert_eq!(PasswordOptions::from_args(&["1025"]).unwrap_err(), "Length must be at most 1024");
+        assert!(PasswordOptions::from_args(&["99999999999"]).is_err());
+        assert_eq!(PasswordOptions::from_args(&["-w", "64"]).unwrap().words, Some(64));
+        assert_eq!(PasswordOptions::from_args(&["-w", "65"]).unwrap_err(), "A passphrase has at most 64 words");
+
+        let options = PasswordOptions { length: MAX_LENGTH + 1, ..Default::default() };
+        assert!(generate_password(&options).is_err());
+        assert!(generate_passphrase(MAX_WORDS + 1, "-").is_err());
+    }
+
+    #[test]
+    fn password_has_every_class() {
+        let password = generate_password(&PasswordOptions { length: 4, ..Default::default() }).unwrap();
+        assert_eq!(password.chars().count(), 4);
+        assert!(password.chars().any(|c| LOWER.contains(c)));
+        assert!(password.chars().any(|c| UPPER.contains(c)));
+        assert!(password.chars().any(|c| DIGITS.contains(c)));
+        assert!(password.chars().any(|c| SYMBOLS.contains(c)));
+    }
+
+    #[test]
+    fn passphrase_has_requested_words() {
+        let options = PasswordOptions::from_args(&["-w", "5", "--sep", "+"]).unwrap();
+        assert_eq!(generate_password(&options).unwrap().split('+').count(), 5);
+    }
+}
@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 #[allow(dead_code)]
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use eframe::epaint::Color32;
-use egui::{CentralPanel, OutputCommand, RichText, ScrollArea};
+use egui::{CentralPanel, RichText, ScrollArea};
 use egui::scroll_area::ScrollBarVisibility;
 use egui_inbox::UiInbox;
 use nwtzlang::{match_arg_to_string, mk_fn, mk_null, mk_object};
@@ -14,36 +14,127 @@ use nwtzlang::lexer::tokenize;
 use nwtzlang::parser::Parser;
 use nwtzlang::runtime::RuntimeVal;
 use nwtzlang::types::ValueType::{NativeFn, Object};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
 use tokio::runtime::Runtime;
-use crate::Page;
+use crate::{storage, Page};
+use crate::binary::ImagePreview;
+use crate::markdown::MarkdownPreview;
+use crate::clipboard::SecretClipboard;
+use crate::editor::{Editor, EditorAction};
+use crate::explorer::{ExplorerCache, ExplorerDialog};
+use crate::fstree::{FsTree, Node, NodeId, ROOT};
+use crate::genpwd::{generate_password, PasswordOptions};
+use crate::history::{push_revision, DiffCache, Origin, Revision};
+use crate::metadata::Metadata;
+use crate::mount::{Backend, HostDir, Mount};
+use crate::pwdfile::{self, parse_pwd_line, Credential, PwdItem};
+use crate::search::{SearchCache, SearchQuery};
+use crate::trash::{in_trash, TRASH};
+use crate::undo::Mutation;
+use crate::totp::Totp;
+use crate::vault::{is_sealed, UnlockForm, Vault};
+use crate::vpath::{validate_name, VPath};
+use crate::watch::HostWatch;
 #[derive(Debug, Clone)]
 pub struct Glob1Env {
-    pub current_path: PathBuf,
-    pub root_content: Vec<FsEntry>,
+    pub current_path: VPath,
+    pub tree: FsTree,
     pub lang_env: Environment,
     pub inbox: Arc<UiInbox<String>>,
     pub execution_started: bool,
     pub message_buffer: Vec<String>,
+    pub storage_path: Option<PathBuf>,
+    pub dirty: bool,
+    pub vault: Vault,
+    pub unlock_form: UnlockForm,
+    pub clipboard: SecretClipboard,
+    pub selected_file: Option<PathBuf>,
+    pub generator_service: String,
+    pub editor: Option<Editor>,
+    pub explorer_dialog: ExplorerDialog,
+    pub explorer_error: Option<String>,
+    pub explorer_notice: Option<String>,
+    pub explorer_details: bool,
+    pub explorer_cache: Option<ExplorerCache>,
+    pub show_hidden: bool,
+    pub search: SearchQuery,
+    pub search_cache: Option<SearchCache>,
+    pub history_open: bool,
+    pub history_selected: Option<usize>,
+    pub history_error: Option<String>,
+    pub history_diff: Option<DiffCache>,
+    pub undo_stack: VecDeque<Mutation>,
+    pub watch: HostWatch,
+    pub image_preview: Option<ImagePreview>,
+    pub markdown_preview: Option<MarkdownPreview>,
+    pub hex_page: usize,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum FsEntry {
     File(File),
     Directory(Directory),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct File {
     pub name: String,
     pub content: String,
     pub system: bool,
+    #[serde(default)]
+    pub metadata: Metadata,
+    /// Versions précédentes, de la plus ancienne à la plus récente
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Revision>,
+    /// Contenu d'un fichier binaire ; `content` est alors vide
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::binary::base64_data")]
+    pub data: Option<Vec<u8>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Directory {
     pub name: String,
     pub content: Vec<FsEntry>,
     pub system: bool,
+    #[serde(default)]
+    pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount: Option<Mount>,
+}
+
+impl FsEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            FsEntry::File(f) => &f.name,
+            FsEntry::Directory(d) => &d.name,
+        }
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        match self {
+            FsEntry::File(f) => f.name = name.to_string(),
+            FsEntry::Directory(d) => d.name = name.to_string(),
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        match self {
+            FsEntry::File(f) => &mut f.metadata,
+            FsEntry::Directory(d) => &mut d.metadata,
+        }
+    }
+
+    fn clear_system(&mut self) {
+        match self {
+            FsEntry::File(f) => f.system = false,
+            FsEntry::Directory(d) => {
+                d.system = false;
+                d.content.iter_mut().for_each(FsEntry::clear_system);
+            }
+        }
+    }
 }
 
 impl Default for Glob1Env {
@@ -53,17 +144,58 @@ impl Default for Glob1Env {
 }
 
 impl Glob1Env {
+    /// Environnement vide ; sans `storage_path`, rien n'est lu ni écrit sur le disque
+    pub fn new(storage_path: Option<PathBuf>, vault: Vault) -> Self {
+        Self {
+            current_path: VPath::root(),
+            tree: FsTree::default(),
+            lang_env: Environment::new(None),
+            inbox: Arc::new(UiInbox::new()),
+            execution_started: false,
+            message_buffer: Vec::new(),
+            storage_path,
+            dirty: false,
+            vault,
+            unlock_form: UnlockForm::default(),
+            clipboard: SecretClipboard::default(),
+            selected_file: None,
+            generator_service: String::new(),
+            editor: None,
+            explorer_dialog: ExplorerDialog::default(),
+            explorer_error: None,
+            explorer_notice: None,
+            explorer_details: false,
+            explorer_cache: None,
+            show_hidden: false,
+            search: SearchQuery::default(),
+            search_cache: None,
+            history_open: false,
+            history_selected: None,
+            history_error: None,
+            history_diff: None,
+            undo_stack: VecDeque::new(),
+            watch: HostWatch::default(),
+            image_preview: None,
+            markdown_preview: None,
+            hex_page: 0,
+        }
+    }
 
     pub fn ui(&mut self, ui: &mut egui::Ui, current_page: &mut Page){
         //let ui_percent = ui.available_width();
 
-        let file_id = ui.make_persistent_id("glob1env_selected_file");
+        if !self.vault.is_unlocked() {
+            self.lock_screen(ui, current_page);
+            return;
+        }
+
         let mut frame_side = egui::Frame::side_top_panel(ui.style());
         frame_side.outer_margin.right = 8;
 
         egui::SidePanel::left("glob1env_explorer").exact_width(ui.available_width()/100.0*37.0).resizable(false).frame(frame_side).show_inside(ui, |ui|{
             ui.horizontal(|ui| {
-                if ui.add(egui::Button::new(RichText::new("<").size(14.0))).clicked() {
+                let back = ui.add(egui::Button::new(RichText::new("<").size(14.0)));
+                if back.clicked() {
                     //println!("CP {:?}", self.current_path);
                     if self.current_path.parent().is_none() {
                         *current_page = Page::Terminal;
@@ -72,37 +204,19 @@ impl Glob1Env {
                     }
 
                 }
+                if let Some(parent) = self.current_path.parent() {
+                    self.drop_target(&back, &parent);
+                }
 
                 ui.heading(self.current_path.to_string_lossy());
 
             });
+            self.search_bar(ui);
+            self.explorer_toolbar(ui);
             ui.separator();
 
             ScrollArea::vertical().auto_shrink(false).max_height(ui.available_height() - 1.5 * ui.spacing().interact_size.y - ui.spacing().item_spacing.y, ).show(ui, |ui| {
-                match self.ls() {
-                    Ok(entries) => {
-                        for entry in entries {
-                            match entry {
-                                FsEntry::Directory(dir) => {
-                                    if ui.selectable_label(false, RichText::new(format!("📁 {}", dir.name)).color(if dir.system { Color32::CYAN } else { Color32::WHITE }).size(14.0), ).clicked() {
-                                        self.push(&dir.name);
-                                    }
-                                }
-                                FsEntry::File(file) => {
-                                    if ui.selectable_label(false, RichText::new(format!("📃 {}", file.name)).color(if file.system { Color32::CYAN } else { Color32::WHITE }).size(14.0)).clicked() {
-                                        ui.ctx().data_mut(|d| {
-                                            d.insert_temp(file_id, file);
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        ui.label("Impossible de lister le répertoire.");
-                        eprintln!("{}", e);
-                    }
-                }
+                self.explorer_list(ui);
             });
         });
 
@@ -114,56 +228,116 @@ impl Glob1Env {
         frame_center.outer_margin.right = 8;
 
         CentralPanel::default().frame(frame_center).show_inside(ui, |ui| {
-            if let Some(file) = ui.ctx().data(|d| d.get_temp::<File>(file_id)) {
+            let selected = self.selected_file.clone()
+                .and_then(|path| self.file(&path).cloned().map(|file| (path, file)));
+            if let Some((file_path, file)) = selected {
+                let editing = self.editor.as_ref().is_some_and(|editor| editor.path == file_path);
                 ui.vertical_centered(|ui| {
                     ui.horizontal(|ui| {
                         ui.heading(RichText::new(&file.name).size(14.0).color(Color32::CYAN));
+                        if !editing && file.data.is_none() && ui.button(RichText::new("edit").size(14.0)).clicked() {
+                            match self.rd_secret(&file_path) {
+                                Ok(content) => self.editor = Some(Editor::new(file_path.clone(), content, file.system, file.metadata.readonly)),
+                                Err(e) => self.explorer_error = Some(e),
+                            }
+                        }
+                        if !editing {
+                            let count = file.history.len();
+                            ui.toggle_value(&mut self.history_open, RichText::new(format!("history ({count})")).size(14.0));
+                        }
                     });
                 });
 
                 ui.separator();
 
+                if editing {
+                    self.editor_ui(ui);
+                    return;
+                }
+                if self.history_open {
+                    ScrollArea::vertical().auto_shrink(false).show(ui, |ui| self.history_ui(ui, &file_path));
+                    return;
+                }
+                if file.data.is_some() {
+                    ScrollArea::vertical().auto_shrink(false).show(ui, |ui| self.binary_ui(ui, &file_path, &file));
+                    return;
+                }
+
                 ScrollArea::vertical().auto_shrink(false).scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden).show(ui, |ui| {
                     
                     let content: Vec<String> = file.content.clone().split('\n').map(String::from).collect();
                     
-                    if file.name.ends_with(".pwd"){
-                        for content in content {
-                            match content.as_str() { 
-                                ctn if ctn.starts_with("[PWD]") => {
-                                    let parts: Vec<&str> = ctn[5..].splitn(2, "::").collect();
-                                    let service = parts.get(0).map(|s| s.trim()).unwrap_or("");
-                                    let secret  = parts.get(1).map(|s| s.trim()).unwrap_or("");
-                                    
-                                    ui.horizontal(|ui| {
-                                        ui.label(RichText::new(format!("{service} :")).size(14.0));
-                                        if ui.button(RichText::new("copy").size(14.0)).clicked() {
-                                            ui.output_mut(|o| {
-                                                o.commands.push(OutputCommand::CopyText(secret.to_string()));
-                                            });
-                                        };
-                                    });
+                    if is_vault_file(&file.name) {
+                        let plaintext = match self.vault.decrypt(&file.content) {
+                            Ok(plaintext) => plaintext,
+                            Err(e) => {
+                                ui.colored_label(Color32::LIGHT_RED, e);
+                                return;
+                            }
+                        };
+                        let pwd_file = pwdfile::parse(&plaintext);
+                        for error in &pwd_file.errors {
+                            ui.colored_label(Color32::LIGHT_RED, format!("ligne {} : {}", error.line, error.message));
+                        }
+                        for item in &pwd_file.items {
+                            match item {
+                                PwdItem::Credential(credential) => Self::credential_ui(ui, &mut self.clipboard, credential),
+                                PwdItem::Totp(line) => match Totp::parse_line(line) {
+                                    Some(Ok(totp)) => {
+                                        let code = totp.code();
+                                        ui.horizontal(|ui| {
+                                            ui.label(RichText::new(format!("{} :", totp.service)).size(14.0));
+                                            ui.label(RichText::new(&code).size(14.0).monospace().color(Color32::LIGHT_GREEN));
+                                            ui.label(RichText::new(format!("{}s", totp.remaining())).size(12.0).color(Color32::GRAY));
+                                            self.clipboard.copy_button(ui, &code);
+                                        });
+                                        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+                                    }
+                                    Some(Err(e)) => {
+                                        ui.colored_label(Color32::LIGHT_RED, format!("[TOTP] {}", e));
+                                    }
+                                    None => {}
                                 },
                                 //ctn if ctn.starts_with("[]") => {
 
                                 //},
-                                ctn => {
-                                    ui.label(RichText::new(ctn).size(14.0));
+                                PwdItem::Text(content) => {
+                                    ui.label(RichText::new(content).size(14.0));
                                 },
                             }
                         }
+
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.generator_service).hint_text("Service").desired_width(ui.available_width() / 2.0));
+                            if ui.button(RichText::new("generate").size(14.0)).clicked() && !self.generator_service.trim().is_empty() {
+                                let result = generate_password(&PasswordOptions::default()).and_then(|password| {
+                                    let credential = Credential::new(self.generator_service.trim(), &password);
+                                    self.wr(&file_path, pwdfile::append_credential(&plaintext, &credential), Origin::Editor)
+                                });
+                                match result {
+                                    Ok(()) => self.generator_service.clear(),
+                                    Err(e) => self.explorer_error = Some(e),
+                                }
+                            }
+                        });
                     }
                     else if file.name.ends_with(".nwtz!") {
                         let file_name = file.name.clone();
-                        let env_clone = self.clone();
-                        let mut lang_env = self.lang_env.clone();
-                        let sender = self.inbox.sender();
+                        let script_path = file_path.clone();
+                        let script_dir = VPath::from(file_path.parent().unwrap_or(Path::new("/")));
 
                         // Seulement démarrer l'exécution si elle n'est pas déjà en cours
                         if !self.execution_started {
                             self.execution_started = true;
+                            self.watch.subscriptions.clear();
+                            // Nouvelle boîte : les messages tardifs d'un script précédent ne sont plus lus
+                            self.inbox = Arc::new(UiInbox::new());
 
-                            let thread_sender = sender.clone();
+                            // Le script est lu ici : le thread n'emporte que son texte, pas l'arborescence
+                            let script = self.rd(&script_path);
+                            let mut lang_env = self.lang_env.clone();
+                            let thread_sender = self.inbox.sender();
 
                             std::thread::spawn(move || {
                                 let rt = Runtime::new().unwrap();
@@ -197,6 +371,36 @@ impl Glob1Env {
                                             Some(NativeFn),
                                         );
 
+                                        // wr("chemin", "contenu") : écrit dans le Glob1Env, relatif au dossier du script
+                                        let wr_sender = thread_sender_clone.clone();
+                                        lang_env.set_var(
+                                            "wr".to_string(),
+                                            mk_fn(Arc::new(move |args, _| {
+                                                if let [path, content, ..] = args.as_slice() {
+                                                    let path = match_arg_to_string(&**path);
+                                                    let content = match_arg_to_string(&**content);
+                                                    let _ = wr_sender.send(format!("[WR] {path}\n{content}"));
+                                                }
+                                                mk_null()
+                                            })),
+                                            Some(NativeFn),
+                                        );
+
+                                        // watch("chemin") : affiche sous le script les changements de l'hôte sous ce chemin monté.
+                                        // L'affichage seulement : le script n'est pas rappelé et ne peut pas lire ces changements.
+                                        let watch_sender = thread_sender_clone.clone();
+                                        lang_env.set_var(
+                                            "watch".to_string(),
+                                            mk_fn(Arc::new(move |args, _| {
+                                                for arg in args {
+                                                    let path = match_arg_to_string(&*arg);
+                                                    let _ = watch_sender.send(format!("[WATCH] {path}"));
+                                                }
+                                                mk_null()
+                                            })),
+                                            Some(NativeFn),
+                                        );
+
                                         let log_sender_ui = thread_sender_clone.clone();
                                         lang_env.set_var(
                                             "ui".to_string(),
@@ -205,6 +409,7 @@ impl Glob1Env {
 
                                                 let button_sender = log_sender_ui.clone();
                                                 let pwd_sender = log_sender_ui.clone();
+                                                let genpwd_sender = log_sender_ui.clone();
 
                                                 props.insert("button".to_string(), mk_fn(Arc::new(move |args, _scope| {
                                                     let text = match_arg_to_string(&*args[0]);
@@ -213,10 +418,35 @@ impl Glob1Env {
                                                 })));
 
                                                 props.insert("password".to_string(), mk_fn(Arc::new(move |args, _scope| {
-                                                    if args.len() == 2 {
-                                                        let service = match_arg_to_string(&*args[0]);
-                                                        let secret = match_arg_to_string(&*args[1]);
-                                                        let _ = pwd_sender.send(format!("[PWD] {service}::{secret}"));
+                                                    match args.len() {
+                                                        // ui.password("service") : secret lu dans les fichiers .pwd du coffre
+                                                        1 => {
+                                                            let service = match_arg_to_string(&*args[0]);
+                                                            let _ = pwd_sender.send(format!("[PWDREF] {service}"));
+                                                        }
+                                                        2 => {
+                                                            let service = match_arg_to_string(&*args[0]);
+                                                            let secret = match_arg_to_string(&*args[1]);
+                                                            let _ = pwd_sender.send(format!("[PWD] {service}::{secret}"));
+                                                        }
+                                                        _ => {}
+                                                    }
+                                                    mk_null()
+                                                })));
+
+                                                // ui.genpassword("service", longueur?) : affiche un nouveau mot de passe à copier
+                                                props.insert("genpassword".to_string(), mk_fn(Arc::new(move |args, _scope| {
+                                                    if let Some(service) = args.first() {
+                                                        let service = match_arg_to_string(&**service);
+                                                        let mut options = PasswordOptions::default();
+                                                        if let Some(length) = args.get(1).and_then(|l| match_arg_to_string(&**l).parse().ok()) {
+                                                            options.length = length;
+                                                        }
+                                                        let message = match generate_password(&options) {
+                                                            Ok(secret) => format!("[PWD] {service}::{secret}"),
+                                                            Err(e) => e,
+                                                        };
+                                                        let _ = genpwd_sender.send(message);
                                                     }
                                                     mk_null()
                                                 })));
@@ -226,12 +456,7 @@ impl Glob1Env {
                                             Some(Object),
                                         );
 
-                                        let rt = tokio::runtime::Handle::current();
-                                        let file_content = rt.block_on(async {
-                                            env_clone.rd(&*PathBuf::from(&file_name))
-                                        });
-
-                                        match file_content {
+                                        match script {
                                             Ok(content) => {
                                                 let tokens = tokenize(content);
                                                 let mut parser = Parser::new(tokens);
@@ -267,6 +492,29 @@ impl Glob1Env {
 
                         // Collecter les nouveaux messages et les ajouter au buffer
                         for msg in self.inbox.as_ref().read(ui) {
+                            // Les écritures du script sont faites ici, sur l'arborescence partagée
+                            let msg = match msg.strip_prefix("[WR] ").and_then(|rest| rest.split_once('\n')) {
+                                Some((path, content)) => {
+                                    let path = script_dir.resolve(path);
+                                    match self.wr(&path, content.to_string(), Origin::Script) {
+                                        Ok(()) => format!("Wrote to `{}`", path.display()),
+                                        Err(e) => e,
+                                    }
+                                }
+                                None => match msg.strip_prefix("[WATCH] ") {
+                                    Some(path) => {
+                                        let path = script_dir.resolve(path);
+                                        if self.mount_target(&path).is_some() {
+                                            let message = format!("Watching `{}`", path.display());
+                                            self.watch.subscriptions.push(path.to_path_buf());
+                                            message
+                                        } else {
+                                            format!("Pas dans un montage : '{}'", path.display())
+                                        }
+                                    }
+                                    None => msg,
+                                },
+                            };
                             if !self.message_buffer.contains(&msg) {
                                 self.message_buffer.push(msg);
                             }
@@ -274,26 +522,37 @@ impl Glob1Env {
 
                         // Afficher tous les messages du buffer persistant
                         for msg in &self.message_buffer {
-                            if let Some(s) = msg.strip_prefix("[PWD] ") {
-                                let parts: Vec<&str> = s.splitn(2, "::").collect();
-                                let service = parts.get(0).map(|s| s.trim()).unwrap_or("");
-                                let secret  = parts.get(1).map(|s| s.trim()).unwrap_or("");
-
+                            if let Some(service) = msg.strip_prefix("[PWDREF] ") {
+                                let secret = self.find_secret(service);
                                 ui.horizontal(|ui| {
                                     ui.label(RichText::new(format!("{service} :")).size(14.0));
-                                    if ui.button(RichText::new("copy").size(14.0)).clicked() {
-                                        ui.output_mut(|o| {
-                                            o.commands.push(OutputCommand::CopyText(secret.to_string()));
-                                        });
-                                    };
+                                    match secret {
+                                        Ok(secret) => {
+                                            self.clipboard.copy_button(ui, &secret);
+                                        }
+                                        Err(e) => {
+                                            ui.colored_label(Color32::LIGHT_RED, e);
+                                        }
+                                    }
+                                });
+                            } else if let Some((service, secret)) = parse_pwd_line(msg) {
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(format!("{service} :")).size(14.0));
+                                    self.clipboard.copy_button(ui, secret);
                                 });
                             } else if let Some(s) = msg.strip_prefix("[BTN] ") {
                                 ui.colored_label(Color32::LIGHT_BLUE, s);
+                            } else if let Some(s) = msg.strip_prefix("[CHANGE] ") {
+                                ui.label(RichText::new(s).size(13.0).monospace().color(Color32::LIGHT_YELLOW));
                             } else {
                                 ui.label(msg);
                             }
                         }
-                    }                    else {
+                    }
+                    else if file.name.ends_with(".md") || file.name.ends_with(".markdown") {
+                        self.markdown_view(ui, &file_path, &file);
+                    }
+                    else {
                         for c in content.iter() {
                             ui.label(RichText::new(c).size(14.0));
                         }
@@ -307,133 +566,218 @@ impl Glob1Env {
         });
     }
 
-    pub fn push(&mut self, segment: &str) {
-        let mut s = self.current_path.to_string_lossy().to_string();
-        if !s.ends_with('/') {
-            s.push('/');
-        }
-        s.push_str(segment);
-        self.current_path = PathBuf::from(s);
-    }
-
-    #[warn(dead_code)]
-    pub fn join_fn(path: &Path, segment: &str) -> PathBuf {
-        let mut s = path.to_string_lossy().to_string();
-        if !s.ends_with('/') {
-            s.push('/');
+    fn editor_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+        match editor.ui(ui) {
+            EditorAction::None => {}
+            EditorAction::Close => self.close_editor(),
+            EditorAction::Save { content, system } => {
+                let path = editor.path.clone();
+                let result = if system { self.wr_system(&path, content, Origin::Editor) } else { self.wr(&path, content, Origin::Editor) };
+                if result.is_ok() {
+                    // Un script modifié est relancé à la prochaine ouverture
+                    self.execution_started = false;
+                    self.message_buffer.clear();
+                }
+                let Some(editor) = self.editor.as_mut() else {
+                    return;
+                };
+                match result {
+                    Ok(()) if editor.confirm_close => self.close_editor(),
+                    Ok(()) => editor.saved(),
+                    Err(e) => editor.error = Some(e),
+                }
+            }
         }
-        s.push_str(segment);
-        PathBuf::from(s)
     }
 
-    pub fn find_directory_mut<'a>(entries: &'a mut [FsEntry], path_parts: &[&str], ) -> Option<&'a mut Directory> {
-        if path_parts.is_empty() {
-
-            return None;
+    fn credential_ui(ui: &mut egui::Ui, clipboard: &mut SecretClipboard, credential: &Credential) {
+        if credential.legacy {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{} :", credential.service)).size(14.0));
+                clipboard.copy_button(ui, &credential.secret);
+            });
+            return;
         }
 
-        let current_part = path_parts[0];
-        let remaining_parts = &path_parts[1..];
-
-        for entry in entries.iter_mut() {
-            if let FsEntry::Directory(dir) = entry {
-                if dir.name == current_part {
-                    return if remaining_parts.is_empty() {
-                        Some(dir)
-                    } else {
-                        Self::find_directory_mut(&mut dir.content, remaining_parts)
-                    }
+        ui.label(RichText::new(&credential.service).size(14.0).strong().color(Color32::CYAN));
+        ui.indent(("credential", credential.line), |ui| {
+            for (label, value) in [("user", &credential.username), ("secret", &credential.secret), ("url", &credential.url)] {
+                if value.is_empty() {
+                    continue;
                 }
+                ui.horizontal(|ui| {
+                    let shown = if label == "secret" { "••••••••" } else { value.as_str() };
+                    ui.label(RichText::new(format!("{label} : {shown}")).size(12.0));
+                    clipboard.copy_button(ui, value);
+                });
             }
-        }
-
-        None
+            if !credential.tags.is_empty() {
+                ui.label(RichText::new(format!("# {}", credential.tags.join(", "))).size(12.0).color(Color32::LIGHT_BLUE));
+            }
+            if !credential.notes.is_empty() {
+                ui.label(RichText::new(&credential.notes).size(12.0));
+            }
+            if let Some(updated) = credential.updated.or(credential.created) {
+                ui.label(RichText::new(format!("updated {}", updated.format("%Y-%m-%d %H:%M"))).size(10.0).color(Color32::GRAY));
+            }
+        });
     }
 
-    pub fn add_entry_to_path(&mut self, path: &Path, entry: FsEntry) -> Result<(), String> {
-        if !path.has_root() {
-            return Err("Le chemin doit être absolu (commencer par '/')".to_string());
-        }
+    fn lock_screen(&mut self, ui: &mut egui::Ui, current_page: &mut Page) {
+        ui.horizontal(|ui| {
+            if ui.add(egui::Button::new(RichText::new("<").size(14.0))).clicked() {
+                *current_page = Page::Terminal;
+            }
+        });
+        ui.separator();
 
-        if path == Path::new("/") {
-            //if let FsEntry::File(ref file) = entry {
-            //    if file.executable {
-            //        self.apps.push(file.clone());
-            //    }
-            //}
-            self.root_content.push(entry);
-            return Ok(());
+        ui.add_space(ui.available_height() / 4.0);
+        let sealed_files = if self.vault.is_initialized() { 0 } else { self.sealed_file_count() };
+        if self.unlock_form.ui(ui, &mut self.vault, sealed_files) {
+            self.seal_plaintext_vault_files();
         }
+    }
 
-        let components: Vec<&str> = path.to_str().unwrap_or("")
-            .split('/')
-            .filter(|&s| !s.is_empty())
-            .collect();
-
-        if let Some(target_dir) = Self::find_directory_mut(&mut self.root_content, &components) {
-            //if let FsEntry::File(ref file) = entry {
-            //    if file.executable {
-            //        self.apps.push(file.clone());
-            //    }
-            //}
-            target_dir.content.push(entry);
-            Ok(())
-        } else {
-            Err(format!("Le chemin '{}' n'a pas été trouvé.", path.display()))
+    /// Verrouille le coffre et efface de la mémoire tout ce qui a été déchiffré
+    pub fn lock(&mut self) {
+        self.vault.lock();
+        self.clipboard.clear_now();
+        self.unlock_form = UnlockForm::default();
+        for msg in self.message_buffer.iter_mut() {
+            msg.zeroize();
         }
+        self.message_buffer.clear();
+        self.execution_started = false;
+        self.close_editor();
+        self.history_diff = None;
     }
 
-    pub fn ls(&self) -> Result<Vec<FsEntry>, String> {
-        let mut res = Vec::new();
-
-        let components = split_components(&self.current_path);
+    /// Ferme l'éditeur et oublie l'état de son `TextEdit`
+    pub fn close_editor(&mut self) {
+        if let Some(editor) = self.editor.take() {
+            editor.close();
+        }
+    }
 
+    /// Chemins dont la lecture demande le coffre déverrouillé
+    pub fn is_protected(path: &Path) -> bool {
+        split_components(path).last().is_some_and(|name| is_vault_file(name))
+    }
 
-        if components.is_empty() {
-            res.extend(self.root_content.iter().cloned());
-            return Ok(res);
+    /// Affiche un fichier dans la vue centrale ; un script quitté continue de tourner
+    /// mais ses messages sont ignorés, il sera relancé à sa prochaine sélection
+    pub fn select(&mut self, path: PathBuf) {
+        if let Some(editor) = &mut self.editor
+            && editor.path != path
+        {
+            if editor.is_dirty() {
+                editor.confirm_close = true;
+                return;
+            }
+            self.close_editor();
+        }
+        if self.selected_file.as_ref() != Some(&path) {
+            self.execution_started = false;
+            self.message_buffer.clear();
+            self.history_selected = None;
+            self.history_error = None;
+            self.history_diff = None;
+            self.hex_page = 0;
+            self.mark_accessed(&path);
+            self.load_mounted(&path);
         }
+        self.selected_file = Some(path);
+    }
 
-        let mut cloned = self.root_content.clone();
-        if let Some(dir) = Self::find_directory_mut(&mut cloned, &components) {
-            res.extend(dir.content.iter().cloned());
-            return Ok(res);
+    /// Se place dans le répertoire du fichier et le sélectionne, ou dans le répertoire lui-même
+    pub fn open(&mut self, path: &Path) {
+        if let Err(e) = self.sync_path(path) {
+            self.explorer_error = Some(e);
+        }
+        if self.is_dir(path) {
+            self.current_path = VPath::from(path);
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            self.current_path = VPath::from(parent);
         }
+        self.select(path.to_path_buf());
+    }
 
-        Err(format!(
-            "Répertoire introuvable : '{}'",
-            self.current_path.display()
-        ))
+    pub fn push(&mut self, segment: &str) {
+        match self.current_path.join(segment) {
+            Ok(path) => {
+                if let Err(e) = self.sync_path(&path) {
+                    self.explorer_error = Some(e);
+                }
+                self.current_path = path;
+            }
+            Err(e) => eprintln!("{}", e),
+        }
     }
 
+    /// Chemin normalisé de l'entrée `segment` dans `path`
     #[warn(dead_code)]
-    pub fn ls_path(&self, path: &Path) -> Result<Vec<FsEntry>, String> {
-        let mut res = Vec::new();
+    pub fn join_fn(path: &Path, segment: &str) -> PathBuf {
+        let mut path = PathBuf::from(VPath::from(path));
+        path.push(segment);
+        path
+    }
 
-        let components = split_components(path);
+    /// Nœud désigné par `path`
+    pub fn lookup(&self, path: &Path) -> Option<NodeId> {
+        self.tree.lookup(&split_components(path))
+    }
 
-        if components.is_empty() {
-            res.extend(self.root_content.iter().cloned());
-            return Ok(res);
-        }
+    pub fn lookup_dir(&self, path: &Path) -> Result<NodeId, String> {
+        self.lookup(path)
+            .filter(|&id| self.tree.node(id).is_dir())
+            .ok_or_else(|| format!("Répertoire introuvable : '{}'", path.display()))
+    }
 
-        let mut cloned = self.root_content.clone();
-        if let Some(dir) = Self::find_directory_mut(&mut cloned, &components) {
-            res.extend(dir.content.iter().cloned());
-            return Ok(res);
-        }
+    /// Ajoute `entry` dans le répertoire `path`, relatif au répertoire courant s'il ne commence pas par `/`
+    pub fn add_entry_to_path(&mut self, path: &Path, entry: FsEntry) -> Result<(), String> {
+        validate_name(entry.name())?;
+        let path = self.current_path.resolve(&path.to_string_lossy());
+        let dir = self.lookup_dir(&path)
+            .map_err(|_| format!("Le chemin '{}' n'a pas été trouvé.", path.display()))?;
+        //if let FsEntry::File(ref file) = entry {
+        //    if file.executable {
+        //        self.apps.push(file.clone());
+        //    }
+        //}
+        self.tree.insert(dir, entry)?;
+        self.dirty = true;
+        Ok(())
+    }
 
-        Err(format!(
-            "Répertoire introuvable : '{}'",
-            path.display()
-        ))
+    /// Contenu du répertoire courant, emprunté à l'arborescence
+    pub fn ls(&mut self) -> Result<Vec<&Node>, String> {
+        self.ls_path(&self.current_path.clone())
     }
 
+    /// Un répertoire monté est relu sur l'hôte
     #[warn(dead_code)]
-    pub fn cd(&mut self, path: &Path) -> &PathBuf {
+    pub fn ls_path(&mut self, path: &Path) -> Result<Vec<&Node>, String> {
+        self.sync_path(path)?;
+        let dir = self.lookup_dir(path)?;
+        Ok(self.tree.children(dir).iter().map(|&id| self.tree.node(id)).collect())
+    }
+
+    pub fn cd(&mut self, path: &Path) -> Result<&VPath, String> {
         //println!("Changement de répertoire vers '{}'", path.display());
-        self.current_path = PathBuf::from(path);
-        &self.current_path
+        self.sync_path(path)?;
+        self.lookup_dir(path)?;
+        self.current_path = VPath::from(path);
+        Ok(&self.current_path)
+    }
+
+    /// Chemin saisi par l'utilisateur, relatif au répertoire courant, avec `.` et `..` résolus
+    pub fn resolve(&self, path: &str) -> VPath {
+        self.current_path.resolve(path)
     }
 
     /*
@@ -454,53 +798,45 @@ impl Glob1Env {
     */    
     
     
-    fn find_directory<'a>(entries: &'a [FsEntry], path_parts: &[&str], ) -> Option<&'a Directory> {
-        if path_parts.is_empty() {
-            return None;
-        }
-        let current = path_parts[0];
-        let rest = &path_parts[1..];
-        for entry in entries {
-            if let FsEntry::Directory(dir) = entry {
-                if dir.name == current {
-                    return if rest.is_empty() {
-                        Some(dir)
-                    } else {
-                        Self::find_directory(&dir.content, rest)
-                    };
-                }
-            }
-        }
-        None
+    pub fn rd(&self, path: &Path) -> Result<String, String> {
+        self.rd_secret(path).map(|content| content.to_string())
     }
 
-    pub fn rd(&self, path: &Path) -> Result<String, String> {
-        let parts = split_components(path);
-        if parts.is_empty() {
+    /// Comme `rd`, mais le contenu, déchiffré pour un `.pwd`, est effacé de la mémoire à sa libération
+    pub fn rd_secret(&self, path: &Path) -> Result<Zeroizing<String>, String> {
+        if split_components(path).is_empty() {
             return Err("Chemin vide".into());
         }
-
-        let (dir_parts, file_name) = parts.split_at(parts.len() - 1);
-        let container = if dir_parts.is_empty() {
-            &self.root_content
-        } else if let Some(dir) = Self::find_directory(&self.root_content, dir_parts) {
-            &dir.content
-        } else {
-            return Err(format!("Répertoire introuvable : '{}'", path.display()));
-        };
-
-        for entry in container {
-            if let FsEntry::File(f) = entry {
-                if f.name == file_name[0] {
-                    return Ok(f.content.clone());
-                }
+        if let Some((mount, rel)) = self.mount_target(path) {
+            let content = HostDir::open(&mount)?.read(&rel)?;
+            if Self::is_protected(path) {
+                return self.vault.decrypt(&content);
             }
+            return Ok(Zeroizing::new(content));
+        }
+        let file = self.file(path).ok_or_else(|| format!("Fichier introuvable : '{}'", path.display()))?;
+        if file.data.is_some() {
+            return Err(format!("Fichier binaire : '{}'", path.display()));
+        }
+        if is_vault_file(&file.name) {
+            return self.vault.decrypt(&file.content);
         }
-        Err(format!("Fichier introuvable : '{}'", path.display()))
+        Ok(Zeroizing::new(file.content.clone()))
     }
 
     #[warn(dead_code)]
-    pub fn wr(&mut self, path: &Path, content: String) -> Result<(), String> {
+    pub fn wr(&mut self, path: &Path, content: impl Into<Zeroizing<String>>, origin: Origin) -> Result<(), String> {
+        self.write(path, content.into(), false, origin)
+    }
+
+    /// Comme `wr`, mais autorise la réécriture d'un fichier système
+    pub fn wr_system(&mut self, path: &Path, content: impl Into<Zeroizing<String>>, origin: Origin) -> Result<(), String> {
+        self.write(path, content.into(), true, origin)
+    }
+
+    /// Le contenu remplacé rejoint l'historique du fichier. Ici le texte en clair d'un `.pwd`
+    /// n'est pas copié hors de son `Zeroizing` ; voir `Editor` pour les copies d'egui.
+    fn write(&mut self, path: &Path, mut content: Zeroizing<String>, allow_system: bool, origin: Origin) -> Result<(), String> {
         let parts = split_components(path);
 
         if parts.is_empty() {
@@ -509,71 +845,413 @@ impl Glob1Env {
 
         let (dir_parts, file_name) = parts.split_at(parts.len() - 1);
 
-        let target_dir = if dir_parts.is_empty() {
-            &mut self.root_content
-        } else if let Some(dir) =
-            Self::find_directory_mut(&mut self.root_content, dir_parts)
-        {
-            &mut dir.content
+        let content = if is_vault_file(file_name[0]) {
+            self.vault.encrypt(&content)?
         } else {
-            return Err(format!("Répertoire introuvable : '{}'", path.display()));
+            std::mem::take(&mut *content)
         };
 
-        for entry in target_dir.iter_mut() {
-            if let FsEntry::File(f) = entry {
-                if f.name == file_name[0] {
-                    f.content = content;
-                    return Ok(());
-                }
+        // Un fichier monté est écrit sur l'hôte, sans historique ni annulation ;
+        // un répertoire virtuel en lecture seule au-dessus du montage le protège aussi
+        if let Some((mount, rel)) = self.mount_target(path) {
+            if let Some(point) = path.ancestors().nth(rel.components().count()).and_then(|point| self.lookup(point)) {
+                self.no_readonly_ancestor(point)?;
             }
+            HostDir::open(&mount)?.write(&rel, &content)?;
+            return self.sync_path(path.parent().unwrap_or(Path::new("/")));
+        }
+
+        let dir = self.tree.lookup(dir_parts)
+            .filter(|&id| self.tree.node(id).is_dir())
+            .ok_or_else(|| format!("Répertoire introuvable : '{}'", path.display()))?;
+        self.writable_dir(dir)?;
+
+        if let Some(id) = self.tree.child(dir, file_name[0]) {
+            return match self.tree.node_mut(id) {
+                Node::File(f) if f.metadata.readonly => {
+                    Err(format!("Fichier en lecture seule : '{}'", path.display()))
+                }
+                Node::File(f) if f.system && !allow_system => {
+                    Err(format!("Fichier système protégé : '{}'", path.display()))
+                }
+                Node::File(f) if f.data.is_some() => {
+                    Err(format!("Fichier binaire : '{}'", path.display()))
+                }
+                Node::File(f) => {
+                    let previous = std::mem::replace(&mut f.content, content);
+                    let previous = push_revision(&mut f.history, origin, previous);
+                    self.mark_modified(id);
+                    self.record(Mutation::Written { path: self.tree.path(id), previous });
+                    self.dirty = true;
+                    Ok(())
+                }
+                Node::Directory(_) => Err(format!("'{}' est un répertoire", path.display())),
+            };
         }
 
+        validate_name(file_name[0])?;
         let new_file = File {
             name: file_name[0].to_string(),
             content,
             //executable: false,
             //args: None,
             system: false,
+            metadata: Metadata::now(),
+            history: Vec::new(),
+            data: None,
         };
-        target_dir.push(FsEntry::File(new_file));
+        let id = self.tree.insert(dir, FsEntry::File(new_file))?;
+        self.mark_modified(dir);
+        self.record(Mutation::Created(self.tree.path(id)));
+        self.dirty = true;
         Ok(())
     }
 
-    //pub fn find_file(&mut self, ,name: &str) -> String{
-    //    
-    //}
+    /// Sauvegarde l'arborescence sur disque si elle a changé depuis la dernière sauvegarde
+    pub fn save_if_dirty(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        if let Some(path) = &self.storage_path
+            && let Err(e) = storage::save(path, &self.tree.to_entries())
+        {
+            eprintln!("{}", e);
+        }
+    }
+
+    /// Chiffre les fichiers `.pwd` encore en clair (anciens fichiers, `touch`, ...)
+    pub fn seal_plaintext_vault_files(&mut self) {
+        for id in self.tree.descendants(ROOT) {
+            if let Node::File(f) = self.tree.node_mut(id)
+                && is_vault_file(&f.name)
+                && !f.content.is_empty()
+                && !is_sealed(&f.content)
+                && let Ok(sealed) = self.vault.encrypt(&f.content)
+            {
+                f.content = sealed;
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Fichiers `.pwd` déjà chiffrés, qui deviennent illisibles sans leur coffre
+    fn sealed_file_count(&self) -> usize {
+        self.vault_file_ids(ROOT).filter(|(_, f)| is_sealed(&f.content)).count()
+    }
+
+    /// Fichiers `.pwd` sous le nœud `id`, lui compris
+    fn vault_file_ids(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &File)> {
+        self.tree.descendants(id).into_iter()
+            .filter_map(|id| self.tree.node(id).as_file().map(|f| (id, f)))
+            .filter(|(_, f)| is_vault_file(&f.name))
+    }
+
+    /// Cherche le secret d'un service dans tous les fichiers `.pwd` du coffre
+    pub fn find_secret(&self, service: &str) -> Result<String, String> {
+        for (_, file) in self.vault_file_ids(ROOT) {
+            let plaintext = self.vault.decrypt(&file.content)?;
+            if let Some(credential) = pwdfile::parse(&plaintext).credentials().find(|c| c.service == service) {
+                return Ok(credential.secret.clone());
+            }
+        }
+        Err(format!("Secret introuvable : '{}'", service))
+    }
+
+    /// Chemins des fichiers `.pwd` : le fichier lui-même, ou tous ceux sous un répertoire
+    pub fn vault_files(&self, path: &Path) -> Result<Vec<PathBuf>, String> {
+        match self.lookup(path) {
+            Some(id) if self.tree.node(id).is_dir() || Self::is_protected(path) => {
+                Ok(self.vault_file_ids(id).map(|(id, _)| self.tree.path(id)).collect())
+            }
+            _ => Err(format!("'{}' n'est ni un fichier .pwd ni un répertoire", path.display())),
+        }
+    }
 
+    /// Identifiants de tous les fichiers renvoyés par `vault_files`, avec leur fichier
+    pub fn credentials(&self, path: &Path) -> Result<Vec<(PathBuf, Credential)>, String> {
+        let mut credentials = Vec::new();
+        for file in self.vault_files(path)? {
+            let parsed = pwdfile::parse(&self.rd(&file)?);
+            credentials.extend(parsed.credentials().map(|c| (file.clone(), c.clone())));
+        }
+        Ok(credentials)
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&File> {
+        self.lookup(path).and_then(|id| self.tree.node(id).as_file())
+    }
 
+    pub fn exists(&self, path: &Path) -> bool {
+        self.lookup(path).is_some()
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.lookup(path).is_some_and(|id| self.tree.node(id).is_dir())
+    }
+
+    pub fn insert_entry(&mut self, path: &Path, mut entry: FsEntry) -> Result<(), String> {
+        let parts = split_components(path);
+        if parts.is_empty() {
+            return Err("Chemin vide".into());
+        }
+        let (dir_parts, name) = parts.split_at(parts.len() - 1);
+        validate_name(name[0])?;
+        let dir = self.tree.lookup(dir_parts)
+            .filter(|&id| self.tree.node(id).is_dir())
+            .ok_or_else(|| format!("Répertoire introuvable : '{}'", path.display()))?;
+
+        if self.tree.child(dir, name[0]).is_some() {
+            return Err(format!("'{}' existe déjà", path.display()));
+        }
+        self.writable_dir(dir)?;
+        entry.set_name(name[0]);
+        let id = self.tree.insert(dir, entry)?;
+        self.mark_modified(dir);
+        self.record(Mutation::Created(self.tree.path(id)));
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Nœud modifiable : existant, pas la racine, sans entrée système ni en lecture seule,
+    /// dans un répertoire modifiable
+    pub fn mutable_node(&self, path: &Path) -> Result<NodeId, String> {
+        let id = self.lookup(path).ok_or_else(|| format!("Introuvable : '{}'", path.display()))?;
+        if id == ROOT {
+            return Err("Impossible de modifier la racine".into());
+        }
+        if self.tree.contains_system(id) {
+            return Err(format!("Entrée système protégée : '{}'", path.display()));
+        }
+        if self.tree.contains_readonly(id) {
+            return Err(format!("Entrée en lecture seule : '{}'", path.display()));
+        }
+        if self.tree.parent(id).and_then(|parent| self.mount_of(parent)).is_some() {
+            return Err(format!("Entrée montée : '{}' (seuls rd et wr sont pris en charge)", path.display()));
+        }
+        if let Some(parent) = self.tree.parent(id) {
+            self.writable_dir(parent)?;
+        }
+        Ok(id)
+    }
+
+    pub fn mkdir(&mut self, path: &Path) -> Result<(), String> {
+        self.insert_entry(path, FsEntry::Directory(Directory {
+            name: String::new(),
+            content: Vec::new(),
+            system: false,
+            metadata: Metadata::now(),
+            mount: None,
+        }))
+    }
+
+    pub fn touch(&mut self, path: &Path) -> Result<(), String> {
+        if self.exists(path) {
+            return Ok(());
+        }
+        self.insert_entry(path, FsEntry::File(File {
+            name: String::new(),
+            content: String::new(),
+            system: false,
+            metadata: Metadata::now(),
+            history: Vec::new(),
+            data: None,
+        }))
+    }
+
+    /// Place l'entrée dans la corbeille ; une entrée déjà dans la corbeille est supprimée définitivement
+    pub fn rm(&mut self, path: &Path, recursive: bool) -> Result<(), String> {
+        if path == Path::new(TRASH) {
+            return Err("La corbeille se vide avec `env trash empty`".into());
+        }
+        let id = self.mutable_node(path)?;
+        if !recursive && !self.tree.children(id).is_empty() {
+            return Err(format!("Répertoire non vide : '{}' (utiliser -r)", path.display()));
+        }
+        if !in_trash(path) {
+            return self.move_to_trash(id).map(|_| ());
+        }
+        let parent = self.tree.parent(id);
+        self.tree.remove(id);
+        if let Some(parent) = parent {
+            self.mark_modified(parent);
+        }
+        self.moved(path, None);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Déplace `from` vers `to`, ou dans `to` si c'est un répertoire existant
+    pub fn mv(&mut self, from: &Path, to: &Path) -> Result<(), String> {
+        let destination = self.destination(from, to)?;
+        if destination.starts_with(from) {
+            return Err(format!("Impossible de déplacer '{}' dans lui-même", from.display()));
+        }
+        if self.exists(&destination) {
+            return Err(format!("'{}' existe déjà", destination.display()));
+        }
+        let id = self.mutable_node(from)?;
+
+        let parts = split_components(&destination);
+        let (dir_parts, name) = parts.split_at(parts.len() - 1);
+        validate_name(name[0])?;
+        let dir = self.tree.lookup(dir_parts)
+            .filter(|&id| self.tree.node(id).is_dir())
+            .ok_or_else(|| format!("Répertoire introuvable : '{}'", destination.display()))?;
+        self.writable_dir(dir)?;
+        let old_parent = self.tree.parent(id);
+        let (previous, metadata) = (self.tree.path(id), self.tree.node(id).metadata().clone());
+        self.tree.relocate(id, dir, name[0])?;
+        self.record(Mutation::Moved { from: previous, to: self.tree.path(id), metadata });
+        for parent in old_parent.into_iter().chain([dir]) {
+            self.mark_modified(parent);
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Copie `from` (et tout son contenu) vers `to`, ou dans `to` si c'est un répertoire existant
+    pub fn cp(&mut self, from: &Path, to: &Path) -> Result<(), String> {
+        let destination = self.destination(from, to)?;
+        if destination.starts_with(from) {
+            return Err(format!("Impossible de copier '{}' dans lui-même", from.display()));
+        }
+        let id = self.lookup(from).ok_or_else(|| format!("Introuvable : '{}'", from.display()))?;
+        let mut entry = self.tree.entry(id);
+        if self.mount_of(id).is_some() {
+            // Seul un fichier monté peut être copié, avec son contenu lu sur l'hôte
+            let FsEntry::File(file) = &mut entry else {
+                return Err(format!("Copie d'un répertoire monté non prise en charge : '{}'", from.display()));
+            };
+            let (mount, rel) = self.mount_of(id).unwrap();
+            file.content = HostDir::open(&mount)?.read(&rel)?;
+        }
+        entry.clear_system();
+        let metadata = entry.metadata_mut();
+        *metadata = Metadata { readonly: metadata.readonly, hidden: metadata.hidden, executable: metadata.executable, ..Metadata::now() };
+        self.insert_entry(&destination, entry)
+    }
+
+    /// Renomme une entrée dans son répertoire ; renvoie le nouveau chemin
+    pub fn rename(&mut self, path: &Path, new_name: &str) -> Result<PathBuf, String> {
+        let new_name = new_name.trim();
+        validate_name(new_name)?;
+        let parent = path.parent().ok_or_else(|| "Impossible de modifier la racine".to_string())?;
+        let destination = Self::join_fn(parent, new_name);
+        if self.exists(&destination) {
+            return Err(format!("'{}' existe déjà", destination.display()));
+        }
+        self.mv(path, &destination)?;
+        Ok(destination)
+    }
+
+    /// Copie une entrée à côté d'elle sous un nom libre (`a copy.txt`, `a copy 2.txt`, ...)
+    pub fn duplicate(&mut self, path: &Path) -> Result<PathBuf, String> {
+        let name = *split_components(path).last()
+            .ok_or_else(|| "Impossible de copier la racine".to_string())?;
+        let parent = path.parent().unwrap_or(Path::new("/"));
+        let (stem, extension) = match name.split_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && !self.is_dir(path) => (stem, format!(".{extension}")),
+            _ => (name, String::new()),
+        };
+
+        let destination = (1..)
+            .map(|n| match n {
+                1 => format!("{stem} copy{extension}"),
+                n => format!("{stem} copy {n}{extension}"),
+            })
+            .map(|candidate| Self::join_fn(parent, &candidate))
+            .find(|candidate| !self.exists(candidate))
+            .expect("un nom libre finit toujours par être trouvé");
+        self.cp(path, &destination)?;
+        Ok(destination)
+    }
+
+    fn destination(&self, from: &Path, to: &Path) -> Result<PathBuf, String> {
+        let name = *split_components(from).last()
+            .ok_or_else(|| "Impossible de modifier la racine".to_string())?;
+        if !self.exists(from) {
+            return Err(format!("Introuvable : '{}'", from.display()));
+        }
+        if self.is_dir(to) {
+            Ok(Self::join_fn(to, name))
+        } else {
+            Ok(to.to_path_buf())
+        }
+    }
+
+    pub fn tree(&self, path: &Path) -> Result<Vec<String>, String> {
+        let dir = self.lookup_dir(path)?;
+        let mut lines = vec![path.display().to_string()];
+        self.tree_lines(dir, "", &mut lines);
+        Ok(lines)
+    }
+
+    fn tree_lines(&self, dir: NodeId, prefix: &str, lines: &mut Vec<String>) {
+        let children = self.tree.children(dir);
+        for (i, &id) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let branch = if last { "└── " } else { "├── " };
+            match self.tree.node(id) {
+                Node::Directory(dir) => {
+                    lines.push(format!("{prefix}{branch}{}/", dir.name));
+                    let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                    self.tree_lines(id, &child_prefix, lines);
+                }
+                Node::File(file) => {
+                    lines.push(format!("{prefix}{branch}{}", file.name));
+                }
+            }
+        }
+    }
+
+
+}
+
+pub fn is_vault_file(name: &str) -> bool {
+    name.ends_with(".pwd")
 }
 
+/// Noms des entrées traversées par `path`, `.` ignoré et `..` remontant d'un niveau
 pub fn split_components(path: &Path) -> Vec<&str> {
     use std::path::Component;
-    path.components()
-        .filter_map(|c| {
-            if let Component::Normal(os_str) = c {
-                os_str.to_str()
-            } else {
-                None
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(os_str) => parts.extend(os_str.to_str()),
+            Component::ParentDir => {
+                parts.pop();
             }
-        })
-        .collect()
+            _ => {}
+        }
+    }
+    parts
 }
 
 pub fn init_system() -> Glob1Env {
-    let mut env = Glob1Env {
-        current_path: PathBuf::from("/"),
-        root_content: Vec::new(),
-        //command_input: String::new(),
-        lang_env: Environment::new(None),
-        inbox: Arc::new(UiInbox::new()),
-        execution_started: false,
-        message_buffer: Vec::new(),
-    };
+    let mut env = Glob1Env::new(storage::data_file(), Vault::load(storage::vault_file()));
+
+    if let Some(path) = env.storage_path.clone() {
+        match storage::load(&path) {
+            Ok(Some(root)) => {
+                env.tree = FsTree::from_entries(root);
+                return env;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // On garde le fichier illisible de côté plutôt que de l'écraser
+                eprintln!("{}", e);
+                let _ = std::fs::rename(&path, path.with_extension("json.bak"));
+            }
+        }
+    }
 
     env.add_entry_to_path(Path::new("/"), FsEntry::Directory(Directory {
         name: "sys".to_string(),
         content: Vec::new(),
         system: true,
+        metadata: Metadata::now(),
+        mount: None,
     })).unwrap();
     
     env.add_entry_to_path(Path::new("/"), FsEntry::File(File {
@@ -581,22 +1259,29 @@ pub fn init_system() -> Glob1Env {
         content: r#"Welcome to glob1env !
 made by Nyutiz"#.to_string(),
         system: false,
+        metadata: Metadata::now(),
+        history: Vec::new(),
+        data: None,
     })).unwrap();
 
     env.add_entry_to_path(Path::new("/"), FsEntry::File(File {
         name: "password.pwd".to_string(),
-        content:
-        r#"[PWD] Google::SuperPassword"#.to_string(),
+        content: String::new(),
         system: false,
+        metadata: Metadata::now(),
+        history: Vec::new(),
+        data: None,
     })).unwrap();
 
     env.add_entry_to_path(Path::new("/"), FsEntry::File(File {
         name: "password.nwtz!".to_string(),
         content:
-        r#"ui.password("GLOBAL", "Put41n2m3r63-!...?44");
-        ui.password("Google01", "Put41n2m3r63-!GOOG01?44");
+        r#"ui.password("Google");
         "#.to_string(),
         system: false,
+        metadata: Metadata { executable: true, ..Metadata::now() },
+        history: Vec::new(),
+        data: None,
     })).unwrap();
 
     env
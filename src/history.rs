@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+use crate::glob1env::Glob1Env;
+use crate::metadata::format_time;
+
+/// Nombre de versions gardées par fichier, les plus anciennes sont oubliées
+pub const MAX_REVISIONS: usize = 20;
+
+/// Au-delà, le diff affiche tout l'ancien contenu retiré et le nouveau ajouté
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Auteur d'une écriture
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    Editor,
+    Terminal,
+    Script,
+    Restore,
+}
+
+/// Contenu d'un fichier avant qu'il soit remplacé, tel que stocké (chiffré pour un `.pwd`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Revision {
+    /// Date du remplacement
+    pub time: DateTime<Utc>,
+    /// Auteur du remplacement
+    pub origin: Origin,
+    pub content: String,
+}
+
+/// Diff affiché par l'historique, recalculé seulement quand la version choisie ou
+/// l'arborescence change. Il contient le texte en clair d'un `.pwd` : effacé au verrouillage.
+#[derive(Clone, Debug)]
+pub struct DiffCache {
+    pub path: PathBuf,
+    pub index: usize,
+    pub generation: u64,
+    /// Lignes préfixées par `+`, `-` ou des espaces, avec leur couleur
+    pub lines: Result<Vec<(Zeroizing<String>, Color32)>, String>,
+    pub identical: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+impl Origin {
+    pub fn label(self) -> &'static str {
+        match self {
+            Origin::Editor => "editor",
+            Origin::Terminal => "terminal",
+            Origin::Script => "script",
+            Origin::Restore => "restore",
+        }
+    }
+}
+
+/// Ajoute l'ancien contenu à l'historique, en oubliant les versions en trop ; renvoie la version ajoutée
+pub fn push_revision(history: &mut Vec<Revision>, origin: Origin, content: String) -> Revision {
+    let revision = Revision { time: Utc::now(), origin, content };
+    history.push(revision.clone());
+    if history.len() > MAX_REVISIONS {
+        history.drain(..history.len() - MAX_REVISIONS);
+    }
+    revision
+}
+
+/// Diff ligne à ligne de `old` vers `new`, par plus longue sous-séquence commune
+pub fn diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Préfixe et suffixe communs, qui couvrent l'essentiel des petites modifications
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|l| DiffLine::Same(l)).collect();
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        lines.extend(a.iter().map(|l| DiffLine::Removed(l)));
+        lines.extend(b.iter().map(|l| DiffLine::Added(l)));
+    } else {
+        // lcs[i][j] : longueur de la sous-séquence commune de a[i..] et b[j..]
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                lines.push(DiffLine::Same(a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                lines.push(DiffLine::Removed(a[i]));
+                i += 1;
+            } else {
+                lines.push(DiffLine::Added(b[j]));
+                j += 1;
+            }
+        }
+        lines.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+        lines.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+    lines
+}
+
+impl Glob1Env {
+    /// Versions précédentes d'un fichier, de la plus ancienne à la plus récente
+    pub fn revisions(&self, path: &Path) -> Result<&[Revision], String> {
+        self.file(path)
+            .map(|file| file.history.as_slice())
+            .ok_or_else(|| format!("Fichier introuvable : '{}'", path.display()))
+    }
+
+    /// Contenu en clair de la version `index`
+    pub fn revision_content(&self, path: &Path, index: usize) -> Result<Zeroizing<String>, String> {
+        let revision = self.revisions(path)?.get(index).ok_or("Version introuvable")?;
+        if Self::is_protected(path) {
+            return self.vault.decrypt(&revision.content);
+        }
+        Ok(Zeroizing::new(revision.content.clone()))
+    }
+
+    /// Réécrit la version `index` ; le contenu remplacé rejoint l'historique
+    pub fn restore(&mut self, path: &Path, index: usize) -> Result<(), String> {
+        let content = self.revision_content(path, index)?;
+        self.wr(path, content.to_string(), Origin::Restore)
+    }
+
+    /// Liste des versions du fichier sélectionné, avec le diff de celle choisie vers le contenu actuel
+    pub fn history_ui(&mut self, ui: &mut egui::Ui, path: &Path) {
+        let revisions: Vec<(DateTime<Utc>, Origin)> = match self.revisions(path) {
+            Ok(revisions) => revisions.iter().map(|r| (r.time, r.origin)).collect(),
+            Err(e) => {
+                ui.colored_label(Color32::LIGHT_RED, e);
+                return;
+            }
+        };
+        if revisions.is_empty() {
+            ui.label(RichText::new("No previous versions").size(12.0).color(Color32::GRAY));
+            return;
+        }
+
+        let mut restore = None;
+        for (index, (time, origin)) in revisions.iter().enumerate().rev() {
+            ui.horizontal(|ui| {
+                let label = format!("{} · replaced by {}", format_time(Some(*time)), origin.label());
+                if ui.selectable_label(self.history_selected == Some(index), RichText::new(label).size(13.0)).clicked() {
+                    self.history_selected = Some(index);
+                }
+                if ui.button(RichText::new("restore").size(12.0)).clicked() {
+                    restore = Some(index);
+                }
+            });
+        }
+        if let Some(index) = restore {
+            match self.restore(path, index) {
+                Ok(()) => {
+                    self.history_selected = None;
+                    self.history_error = None;
+                }
+                Err(e) => self.history_error = Some(e),
+            }
+        }
+        if let Some(error) = &self.history_error {
+            ui.colored_label(Color32::LIGHT_RED, RichText::new(error).size(12.0));
+        }
+
+        let Some(index) = self.history_selected else {
+            return;
+        };
+        ui.separator();
+        let fresh = self.history_diff.as_ref()
+            .is_some_and(|d| d.path == path && d.index == index && d.generation == self.tree.generation());
+        if !fresh {
+            self.history_diff = Some(self.diff_cache(path, index));
+        }
+        let Some(cache) = &self.history_diff else {
+            return;
+        };
+        match &cache.lines {
+            Ok(lines) => {
+                if cache.identical {
+                    ui.label(RichText::new("Identical to the current content").size(12.0).color(Color32::GRAY));
+                }
+                for (text, color) in lines {
+                    ui.label(RichText::new(text.as_str()).size(13.0).monospace().color(*color));
+                }
+            }
+            Err(e) => {
+                ui.colored_label(Color32::LIGHT_RED, e);
+            }
+        }
+    }
+
+    /// Diff de la version `index` vers le contenu actuel, mis en forme pour `history_ui`
+    fn diff_cache(&self, path: &Path, index: usize) -> DiffCache {
+        let mut identical = false;
+        let lines = self.revision_content(path, index)
+            .and_then(|old| Ok((old, self.rd_secret(path)?)))
+            .map(|(old, current)| {
+                let lines = diff(&old, &current);
+                identical = lines.iter().all(|line| matches!(line, DiffLine::Same(_)));
+                lines.into_iter()
+                    .map(|line| match line {
+                        DiffLine::Same(l) => (Zeroizing::new(format!("  {}", l)), Color32::GRAY),
+                        DiffLine::Added(l) => (Zeroizing::new(format!("+ {}", l)), Color32::LIGHT_GREEN),
+                        DiffLine::Removed(l) => (Zeroizing::new(format!("- {}", l)), Color32::LIGHT_RED),
+                    })
+                    .collect()
+            });
+        DiffCache {
+            path: path.to_path_buf(),
+            index,
+            generation: self.tree.generation(),
+            lines,
+            identical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_identical() {
+        let lines = diff("a\nb\nc", "a\nb\nc");
+        assert_eq!(lines, vec![DiffLine::Same("a"), DiffLine::Same("b"), DiffLine::Same("c")]);
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_insert() {
+        assert_eq!(diff("a\nc", "a\nb\nc"), vec![DiffLine::Same("a"), DiffLine::Added("b"), DiffLine::Same("c")]);
+        assert_eq!(diff("", "a\nb"), vec![DiffLine::Added("a"), DiffLine::Added("b")]);
+        assert_eq!(diff("a", "a\nb"), vec![DiffLine::Same("a"), DiffLine::Added("b")]);
+    }
+
+    #[test]
+    fn diff_delete() {
+        assert_eq!(diff("a\nb\nc", "a\nc"), vec![DiffLine::Same("a"), DiffLine::Removed("b"), DiffLine::Same("c")]);
+        assert_eq!(diff("a\nb", ""), vec![DiffLine::Removed("a"), DiffLine::Removed("b")]);
+    }
+
+    #[test]
+    fn diff_replace() {
+        assert_eq!(
+            diff("a\nb\nc", "a\nx\nc"),
+            vec![DiffLine::Same("a"), DiffLine::Removed("b"), DiffLine::Added("x"), DiffLine::Same("c")],
+        );
+    }
+
+    #[test]
+    fn diff_interleaved() {
+        let lines = diff("a\nb\nc\nd", "b\nx\nd\ne");
+        assert_eq!(lines, vec![
+            DiffLine::Removed("a"),
+            DiffLine::Same("b"),
+            DiffLine::Removed("c"),
+            DiffLine::Added("x"),
+            DiffLine::Same("d"),
+            DiffLine::Added("e"),
+        ]);
+    }
+
+    #[test]
+    fn diff_oversized_falls_back_to_remove_then_add() {
+        let old: Vec<String> = (0..2_001).map(|i| format!("old {i}")).collect();
+        let new: Vec<String> = (0..2_001).map(|i| format!("new {i}")).collect();
+        let (old, new) = (format!("head\n{}\ntail", old.join("\n")), format!("head\n{}\ntail", new.join("\n")));
+
+        let lines = diff(&old, &new);
+        assert_eq!(lines.len(), 2 + 2 * 2_001);
+        assert_eq!(lines[0], DiffLine::Same("head"));
+        assert!(lines[1..=2_001].iter().all(|line| matches!(line, DiffLine::Removed(_))));
+        assert!(lines[2_002..=4_002].iter().all(|line| matches!(line, DiffLine::Added(_))));
+        assert_eq!(lines[4_003], DiffLine::Same("tail"));
+    }
+
+    #[test]
+    fn push_revision_keeps_the_latest() {
+        let mut history = Vec::new();
+        for i in 0..MAX_REVISIONS + 5 {
+            push_revision(&mut history, Origin::Terminal, format!("v{i}"));
+        }
+        assert_eq!(history.len(), MAX_REVISIONS);
+        assert_eq!(history[0].content, "v5");
+        assert_eq!(history[MAX_REVISIONS - 1].content, format!("v{}", MAX_REVISIONS + 4));
+        assert_eq!(history[0].origin, Origin::Terminal);
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+use crate::pwdfile::{self, parse_timestamp, Credential, PwdFile, PwdItem};
+
+/// Que faire d'un identifiant importé dont le service et l'utilisateur existent déjà
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Duplicates {
+    Skip,
+    Overwrite,
+    Keep,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportedEntry {
+    pub credential: Credential,
+    /// Secret ou URI otpauth:// ; ajouté comme ligne `[TOTP]`
+    pub totp: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn lines(&self, dry_run: bool) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{}{} added, {} overwritten, {} duplicates skipped",
+            if dry_run { "Dry run: " } else { "" },
+            self.added.len(),
+            self.overwritten.len(),
+            self.skipped.len(),
+        )];
+        lines.extend(self.added.iter().map(|s| format!("  + {s}")));
+        lines.extend(self.overwritten.iter().map(|s| format!("  ~ {s}")));
+        lines.extend(self.skipped.iter().map(|s| format!("  = {s}")));
+        lines
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Lit un export Bitwarden (JSON) ou un CSV KeePass / KeePassXC / Chromium / Bitwarden
+pub fn read_host_file(path: &Path) -> Result<Vec<ImportedEntry>, String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("Lecture de '{}' impossible : {}", path.display(), e))?;
+
+    if raw.trim_start().starts_with('{') {
+        parse_bitwarden_json(&raw)
+    } else {
+        parse_csv(&raw)
+    }
+}
+
+fn parse_bitwarden_json(raw: &str) -> Result<Vec<ImportedEntry>, String> {
+    let root: Value = serde_json::from_str(raw).map_err(|e| format!("JSON invalide : {}", e))?;
+    if root["encrypted"].as_bool() == Some(true) {
+        return Err("Les exports Bitwarden chiffrés ne sont pas supportés".into());
+    }
+
+    let folders: Vec<(&str, &str)> = root["folders"].as_array().into_iter().flatten()
+        .filter_map(|f| Some((f["id"].as_str()?, f["name"].as_str()?)))
+        .collect();
+    let text = |v: &Value| v.as_str().unwrap_or_default().to_string();
+
+    let items = root["items"].as_array().ok_or("Champ 'items' manquant")?;
+    Ok(items.iter()
+        // type 1 : identifiant ; les cartes, notes et identités sont ignorées
+        .filter(|item| item["type"].as_u64() == Some(1))
+        .map(|item| {
+            let login = &item["login"];
+            let folder = folders.iter().find(|(id, _)| Some(*id) == item["folderId"].as_str()).map(|(_, name)| name.to_string());
+            ImportedEntry {
+                credential: Credential {
+                    service: text(&item["name"]),
+                    username: text(&login["username"]),
+                    secret: text(&login["password"]),
+                    url: login["uris"].get(0).map(|u| text(&u["uri"])).unwrap_or_default(),
+                    tags: folder.into_iter().collect(),
+                    notes: text(&item["notes"]),
+                    created: item["creationDate"].as_str().and_then(|t| parse_timestamp(t).ok()),
+                    updated: item["revisionDate"].as_str().and_then(|t| parse_timestamp(t).ok()),
+                    ..Default::default()
+                },
+                totp: text(&login["totp"]),
+            }
+        })
+        .collect())
+}
+
+fn parse_csv(raw: &str) -> Result<Vec<ImportedEntry>, String> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(raw.as_bytes());
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| format!("CSV invalide : {}", e))?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    // Noms de colonnes des différents gestionnaires, par champ
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let service = column(&["title", "name", "account"]);
+    let username = column(&["username", "login name", "login_username", "user name"]);
+    let secret = column(&["password", "login_password"]);
+    let url = column(&["url", "web site", "login_uri"]);
+    let notes = column(&["notes", "note", "comments"]);
+    let group = column(&["group", "folder"]);
+    let totp = column(&["totp", "login_totp"]);
+    let created = column(&["created", "creation time"]);
+    let updated = column(&["last modified", "last modification time"]);
+
+    let (Some(service), Some(secret)) = (service, secret) else {
+        return Err("Colonnes 'name'/'title' et 'password' introuvables".into());
+    };
+
+    let mut entries = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("ligne {} : {}", index + 2, e))?;
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or_default().to_string();
+        let time = |col: Option<usize>| col.and_then(|c| record.get(c)).and_then(|t| parse_timestamp(t).ok());
+
+        entries.push(ImportedEntry {
+            credential: Credential {
+                service: field(Some(service)),
+                username: field(username),
+                secret: field(Some(secret)),
+                url: field(url),
+                tags: Some(field(group)).filter(|g| !g.is_empty()).into_iter().collect(),
+                notes: field(notes),
+                created: time(created),
+                updated: time(updated),
+                ..Default::default()
+            },
+            totp: field(totp),
+        });
+    }
+    Ok(entries)
+}
+
+fn same_account(a: &Credential, b: &Credential) -> bool {
+    a.service.eq_ignore_ascii_case(&b.service) && a.username.eq_ignore_ascii_case(&b.username)
+}
+
+fn describe(credential: &Credential) -> String {
+    if credential.username.is_empty() {
+        credential.service.clone()
+    } else {
+        format!("{} ({})", credential.service, credential.username)
+    }
+}
+
+/// Service d'une ligne `[TOTP] service::secret`
+fn totp_service(line: &str) -> Option<&str> {
+    let (service, _) = line.strip_prefix("[TOTP]")?.split_once("::")?;
+    Some(service.trim())
+}
+
+/// Remplace la ligne `[TOTP]` du service, ou l'ajoute s'il n'en a pas
+fn set_totp(file: &mut PwdFile, service: &str, totp: &str) {
+    let line = format!("[TOTP] {}::{}", service, totp);
+    let existing = file.items.iter_mut().find_map(|item| match item {
+        PwdItem::Totp(existing) if totp_service(existing).is_some_and(|s| s.eq_ignore_ascii_case(service)) => Some(existing),
+        _ => None,
+    });
+    match existing {
+        Some(existing) => *existing = line,
+        None => file.items.push(PwdItem::Totp(line)),
+    }
+}
+
+/// Fusionne les identifiants importés dans le contenu déchiffré d'un fichier `.pwd`.
+/// Le fichier étant réécrit depuis son analyse, il doit être sans erreur pour ne rien perdre.
+pub fn merge(existing: &str, incoming: Vec<ImportedEntry>, duplicates: Duplicates) -> Result<(String, ImportReport), String> {
+    let mut file = pwdfile::parse(existing);
+    if let Some(error) = file.errors.first() {
+        return Err(format!("Le fichier contient des erreurs (ligne {} : {}), corrigez-le avant d'importer", error.line, error.message));
+    }
+    let mut report = ImportReport::default();
+
+    for entry in incoming {
+        let mut credential = entry.credential;
+        let now = chrono::Utc::now();
+        credential.created.get_or_insert(now);
+        credential.updated.get_or_insert(now);
+
+        let duplicate = file.credentials_mut().find(|c| same_account(c, &credential));
+        match (duplicate, duplicates) {
+            (Some(_), Duplicates::Skip) => {
+                report.skipped.push(describe(&credential));
+                continue;
+            }
+            (Some(existing), Duplicates::Overwrite) => {
+                report.overwritten.push(describe(&credential));
+                credential.created = existing.created.or(credential.created);
+                credential.line = existing.line;
+                let service = credential.service.clone();
+                *existing = credential;
+                if !entry.totp.is_empty() {
+                    set_totp(&mut file, &service, &entry.totp);
+                }
+            }
+            _ => {
+                report.added.push(describe(&credential));
+                let service = credential.service.clone();
+                file.items.push(PwdItem::Credential(credential));
+                if !entry.totp.is_empty() {
+                    file.items.push(PwdItem::Totp(format!("[TOTP] {}::{}", service, entry.totp)));
+                }
+            }
+        }
+    }
+
+    Ok((file.to_content(), report))
+}
+
+/// Identifiants d'un contenu `.pwd` déchiffré, à exporter. La ligne `[TOTP] service::...`
+/// d'un service est rattachée à son premier identifiant, comme à l'import.
+pub fn exported_entries(content: &str) -> Vec<ImportedEntry> {
+    let file = pwdfile::parse(content);
+    let mut totps: Vec<(&str, &str)> = file.items.iter()
+        .filter_map(|item| match item {
+            PwdItem::Totp(line) => line.strip_prefix("[TOTP]")?.split_once("::").map(|(s, v)| (s.trim(), v.trim())),
+            _ => None,
+        })
+        .collect();
+
+    file.credentials()
+        .map(|credential| {
+            let totp = totps.iter()
+                .position(|(service, _)| service.eq_ignore_ascii_case(&credential.service))
+                .map(|index| totps.remove(index).1.to_string())
+                .unwrap_or_default();
+            ImportedEntry { credential: credential.clone(), totp }
+        })
+        .collect()
+}
+
+/// CSV compatible Chromium (`name,url,username,password,note`) avec les tags, le TOTP et les dates en plus
+pub fn export_csv(entries: &[ImportedEntry]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["name", "url", "username", "password", "note", "tags", "totp", "created", "updated"])
+        .map_err(|e| e.to_string())?;
+    for entry in entries {
+        let c = &entry.credential;
+        let time = |t: Option<chrono::DateTime<chrono::Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+        writer.write_record([
+            c.service.as_str(),
+            c.url.as_str(),
+            c.username.as_str(),
+            c.secret.as_str(),
+            c.notes.as_str(),
+            &c.tags.join(", "),
+            entry.totp.as_str(),
+            &time(c.created),
+            &time(c.updated),
+        ]).map_err(|e| e.to_string())?;
+    }
+    let data = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(data).map_err(|e| e.to_string())
+}
+
+/// JSON au format d'export Bitwarden non chiffré, pour pouvoir être réimporté
+pub fn export_json(entries: &[ImportedEntry]) -> String {
+    let items: Vec<Value> = entries.iter().map(|ImportedEntry { credential: c, totp }| json!({
+        "type": 1,
+        "name": c.service,
+        "notes": if c.notes.is_empty() { Value::Null } else { json!(c.notes) },
+        "login": {
+            "username": c.username,
+            "password": c.secret,
+            "totp": if totp.is_empty() { Value::Null } else { json!(totp) },
+            "uris": if c.url.is_empty() { json!([]) } else { json!([{ "uri": c.url }]) },
+        },
+        "creationDate": c.created.map(|t| t.to_rfc3339()),
+        "revisionDate": c.updated.map(|t| t.to_rfc3339()),
+    })).collect();
+
+    serde_json::to_string_pretty(&json!({ "encrypted": false, "items": items })).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "#pwd v1\n[PWD] legacy::old\n[ENTRY]\nservice: Mail\nusername: me\nsecret: hunter2\nurl: https://mail.example\n[END]\n[TOTP] mail::JBSWY3DPEHPK3PXP\n[TOTP] orphan::GEZDGNBV";
+
+    fn reimport(data: &str) -> Vec<ImportedEntry> {
+        if data.trim_start().starts_with('{') { parse_bitwarden_json(data).unwrap() } else { parse_csv(data).unwrap() }
+    }
+
+    #[test]
+    fn totp_follows_its_service() {
+        let entries = exported_entries(CONTENT);
+        let pairs: Vec<_> = entries.iter().map(|e| (e.credential.service.as_str(), e.totp.as_str())).collect();
+        assert_eq!(pairs, [("legacy", ""), ("Mail", "JBSWY3DPEHPK3PXP")]);
+    }
+
+    #[test]
+    fn export_round_trip_keeps_totp() {
+        let entries = exported_entries(CONTENT);
+        for data in [export_csv(&entries).unwrap(), export_json(&entries)] {
+            let imported = reimport(&data);
+            assert_eq!(imported.len(), 2);
+            assert_eq!(imported[1].credential.secret, "hunter2");
+            assert_eq!(imported[1].credential.url, "https://mail.example");
+            assert_eq!(imported[1].totp, "JBSWY3DPEHPK3PXP");
+            assert_eq!(imported[0].totp, "");
+
+            let (content, report) = merge("", imported, Duplicates::Skip).unwrap();
+            assert_eq!(report.added.len(), 2);
+            assert!(content.contains("[TOTP] Mail::JBSWY3DPEHPK3PXP"));
+        }
+    }
+}
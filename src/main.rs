@@ -3,16 +3,44 @@ mod frame;
 mod terminal;
 mod settings;
 mod glob1env;
+mod commands;
+mod argv;
+mod storage;
+mod vault;
+mod clipboard;
+mod genpwd;
+mod totp;
+mod pwdfile;
+mod import_export;
+mod audit;
+mod editor;
+mod explorer;
+mod vpath;
+mod fstree;
+mod metadata;
+mod search;
+mod history;
+mod trash;
+mod undo;
+mod mount;
+mod watch;
+mod archive;
+mod binary;
+mod markdown;
+mod uri;
 
 use eframe::{egui};
 use std::process::exit;
+use std::time::{Duration, Instant};
 use eframe::glow::Context;
 use egui::{Id, Style, Visuals};
 use egui::{Color32};
+use crate::commands::CommandRegistry;
 use crate::frame::custom_window_frame;
 use crate::glob1env::Glob1Env;
 use crate::settings::Settings;
 use crate::terminal::Terminal;
+use crate::undo::UNDO_SHORTCUT;
 
 const MAX_HISTORY_LINES: usize = 100;
 const PROMPT: &str = "> ";
@@ -62,6 +90,17 @@ enum Page {
     Settings,
     Glob1Env,
 }
+
+impl Page {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Page::Terminal => "Terminal",
+            Page::Settings => "Settings",
+            Page::Glob1Env => "Environment",
+        }
+    }
+}
+
 #[derive(Clone)]
 struct NwtzUi {
     title: String,
@@ -69,12 +108,14 @@ struct NwtzUi {
     terminal: Terminal,
     settings: Settings,
     glob1env: Glob1Env,
+    commands: CommandRegistry,
     is_minimized: bool,
     normal_size: egui::Vec2,
     minimized_size: egui::Vec2,
     normal_pos: egui::Pos2,
     minimized_pos: egui::Pos2,
     position_initialized: bool,
+    last_activity: Instant,
     //pages: Vec<Page> Peut etre dans le futur pour la modulabilité
 }
 
@@ -86,12 +127,14 @@ impl Default for NwtzUi {
             terminal: Default::default(),
             settings: Default::default(),
             glob1env: Default::default(),
+            commands: Default::default(),
             is_minimized: false,
             normal_size: egui::Vec2::new(400.0, 200.0),
             minimized_size: egui::Vec2::new(60.0, 60.0),
             normal_pos: egui::Pos2::new(0.0, 0.0),
             minimized_pos: egui::Pos2::new(0.0, 0.0),
             position_initialized: false,
+            last_activity: Instant::now(),
         }
     }
 }
@@ -114,6 +157,12 @@ impl eframe::App for NwtzUi {
             }
         }
         
+        self.auto_lock(ctx);
+        self.undo_shortcut(ctx);
+        self.glob1env.clipboard.delay = Duration::from_secs(self.settings.clipboard_clear_secs as u64);
+        self.glob1env.clipboard.tick(ctx);
+        self.glob1env.poll_changes(ctx);
+
         if self.is_minimized {
             self.show_minimized_ui(ctx);
             return;
@@ -137,10 +186,13 @@ impl eframe::App for NwtzUi {
                 mem.data.insert_persisted::<Option<Page>>(Id::from("next_page"), None);
             });
         }
+
+        self.glob1env.save_if_dirty();
     }
     
 
     fn on_exit(&mut self, _gl: Option<&Context>) {
+        self.glob1env.save_if_dirty();
         exit(0)
     }
 
@@ -150,6 +202,49 @@ impl eframe::App for NwtzUi {
 }
 
 impl NwtzUi {
+    fn auto_lock(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.events.is_empty()) {
+            self.last_activity = Instant::now();
+        }
+        if !self.glob1env.vault.is_unlocked() {
+            return;
+        }
+
+        let timeout = Duration::from_secs(self.settings.auto_lock_minutes as u64 * 60);
+        let idle = self.last_activity.elapsed();
+        if idle >= timeout {
+            self.lock();
+        } else {
+            ctx.request_repaint_after(timeout - idle);
+        }
+    }
+
+    /// Verrouille le coffre et masque les secrets restés dans le terminal
+    fn lock(&mut self) {
+        self.glob1env.lock();
+        self.terminal.redact_secrets();
+    }
+
+    /// Ctrl+Z sur la page Glob1Env, hors d'un champ de saisie, annule la dernière modification
+    fn undo_shortcut(&mut self, ctx: &egui::Context) {
+        if !matches!(self.current_page, Page::Glob1Env) || !self.glob1env.vault.is_unlocked() || ctx.wants_keyboard_input() {
+            return;
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+            let env = &mut self.glob1env;
+            match env.undo() {
+                Ok(done) => {
+                    env.explorer_notice = Some(done);
+                    env.explorer_error = None;
+                }
+                Err(e) => {
+                    env.explorer_notice = None;
+                    env.explorer_error = Some(e);
+                }
+            }
+        }
+    }
+
     fn toggle_minimize(&mut self, ctx: &egui::Context) {
         if !self.is_minimized {
             if let Some(outer) = ctx.input(|i| i.viewport().outer_rect) {
@@ -158,6 +253,8 @@ impl NwtzUi {
         }
         self.is_minimized = !self.is_minimized;
         if self.is_minimized {
+            self.lock();
+
             let size = self.minimized_size;
             let mut pos = self.minimized_pos;
 
@@ -217,8 +314,9 @@ impl NwtzUi {
 
         match self.current_page {
             Page::Terminal => {
-                let mut clone_ui = self.clone();
-                self.terminal.ui(ui, &mut clone_ui);
+                let mut terminal = std::mem::take(&mut self.terminal);
+                terminal.ui(ui, self);
+                self.terminal = terminal;
             }
             Page::Settings => self.settings.ui(ui, &mut self.current_page),
             Page::Glob1Env => self.glob1env.ui(ui, &mut self.current_page),
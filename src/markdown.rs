@@ -0,0 +1,412 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use crate::glob1env::{File, Glob1Env};
+use crate::history::Origin;
+use crate::uri::percent_decode;
+use crate::vpath::VPath;
+
+const TEXT_SIZE: f32 = 14.0;
+/// Décalage d'un niveau de liste ou de citation
+const INDENT: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Style {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Inline {
+    Text(String, Style),
+    Code(String),
+    Link { text: String, url: String, style: Style },
+    Break,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Marker {
+    Bullet,
+    Number(u64),
+    /// Case à cocher ; `offset` est la position de `[ ]` dans le source
+    Task { checked: bool, offset: usize },
+}
+
+/// Ligne de texte : paragraphe, titre ou élément de liste
+#[derive(Clone, Debug, PartialEq)]
+struct Line {
+    depth: usize,
+    quote: bool,
+    heading: Option<HeadingLevel>,
+    marker: Option<Marker>,
+    inlines: Vec<Inline>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Block {
+    Line(Line),
+    Code { depth: usize, text: String },
+    /// La première ligne est l'en-tête
+    Table(Vec<Vec<Vec<Inline>>>),
+    Rule,
+}
+
+/// Blocs du fichier Markdown affiché, gardés tant que son contenu ne change pas
+#[derive(Clone, Debug)]
+pub struct MarkdownPreview {
+    path: PathBuf,
+    size: usize,
+    modified: Option<DateTime<Utc>>,
+    blocks: Arc<[Block]>,
+}
+
+/// Clic dans le rendu d'un fichier Markdown
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkdownAction {
+    /// Case à cocher dont `[ ]` commence à ce décalage du source
+    Toggle(usize),
+    Open(String),
+}
+
+/// État du découpage en blocs
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    inlines: Vec<Inline>,
+    style: Style,
+    link: Option<String>,
+    heading: Option<HeadingLevel>,
+    /// Prochain numéro de chaque liste ouverte, `None` pour une liste à puces
+    lists: Vec<Option<u64>>,
+    marker: Option<Marker>,
+    quote: usize,
+    code: Option<String>,
+    table: Option<Vec<Vec<Vec<Inline>>>>,
+    row: Vec<Vec<Inline>>,
+}
+
+impl Builder {
+    fn flush(&mut self) {
+        if self.inlines.is_empty() && self.marker.is_none() {
+            return;
+        }
+        self.blocks.push(Block::Line(Line {
+            depth: self.lists.len().saturating_sub(1) + self.quote,
+            quote: self.quote > 0,
+            heading: self.heading,
+            marker: self.marker.take(),
+            inlines: std::mem::take(&mut self.inlines),
+        }));
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(code) = &mut self.code {
+            code.push_str(text);
+            return;
+        }
+        let inline = match &self.link {
+            Some(url) => Inline::Link { text: text.to_string(), url: url.clone(), style: self.style },
+            None => Inline::Text(text.to_string(), self.style),
+        };
+        self.inlines.push(inline);
+    }
+
+    fn event(&mut self, event: Event, range: Range<usize>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Html(text) | Event::InlineHtml(text) => self.text(&text),
+            Event::Code(code) => self.inlines.push(Inline::Code(code.to_string())),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.inlines.push(Inline::Break),
+            Event::Rule => {
+                self.flush();
+                self.blocks.push(Block::Rule);
+            }
+            Event::TaskListMarker(checked) => self.marker = Some(Marker::Task { checked, offset: range.start }),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.heading = Some(level);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                // Texte d'un élément avant sa sous-liste
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                self.marker = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        Marker::Number(*number - 1)
+                    }
+                    _ => Marker::Bullet,
+                });
+            }
+            Tag::Table(_) => {
+                self.flush();
+                self.table = Some(Vec::new());
+            }
+            Tag::TableHead | Tag::TableRow => self.row.clear(),
+            Tag::TableCell => self.inlines.clear(),
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link { dest_url, .. } => self.link = Some(dest_url.to_string()),
+            Tag::Image { dest_url, .. } => {
+                self.link = Some(dest_url.to_string());
+                self.text("🖼 ");
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote = self.quote.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                let text = self.code.take().unwrap_or_default();
+                let depth = self.lists.len() + self.quote;
+                self.blocks.push(Block::Code { depth, text: text.trim_end_matches('\n').to_string() });
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.inlines);
+                self.row.push(cell);
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                let row = std::mem::take(&mut self.row);
+                if let Some(table) = &mut self.table {
+                    table.push(row);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.blocks.push(Block::Table(table));
+                }
+            }
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strikethrough = false,
+            TagEnd::Link | TagEnd::Image => self.link = None,
+            _ => {}
+        }
+    }
+}
+
+fn parse(source: &str) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+    let mut builder = Builder::default();
+    for (event, range) in Parser::new_ext(source, options).into_offset_iter() {
+        builder.event(event, range);
+    }
+    builder.flush();
+    builder.blocks
+}
+
+/// Source avec la case à cocher de `offset` inversée
+pub fn toggle_task(source: &str, offset: usize) -> Option<String> {
+    let open = offset + source.get(offset..)?.find('[')?;
+    let mark = open + 1;
+    let checked = match source.get(mark..mark + 1)? {
+        " " => "x",
+        "x" | "X" => " ",
+        _ => return None,
+    };
+    Some(format!("{}{}{}", &source[..mark], checked, &source[mark + 1..]))
+}
+
+/// Lien externe, ouvert dans le navigateur plutôt que dans le Glob1Env
+fn is_external(url: &str) -> bool {
+    url.contains("://") || url.starts_with("mailto:")
+}
+
+fn rich(text: &str, style: Style, size: f32) -> RichText {
+    let mut text = RichText::new(text).size(size);
+    if style.strong {
+        text = text.strong();
+    }
+    if style.emphasis {
+        text = text.italics();
+    }
+    if style.strikethrough {
+        text = text.strikethrough();
+    }
+    text
+}
+
+fn heading_size(level: HeadingLevel) -> f32 {
+    match level {
+        HeadingLevel::H1 => 22.0,
+        HeadingLevel::H2 => 19.0,
+        HeadingLevel::H3 => 17.0,
+        _ => 15.0,
+    }
+}
+
+fn inlines_ui(ui: &mut egui::Ui, inlines: &[Inline], size: f32, heading: bool, action: &mut Option<MarkdownAction>) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text, style) => {
+                let style = Style { strong: style.strong || heading, ..*style };
+                ui.label(rich(text, style, size));
+            }
+            Inline::Code(code) => {
+                ui.label(RichText::new(code).size(size - 1.0).code());
+            }
+            Inline::Link { text, url, style } => {
+                if ui.link(rich(text, *style, size)).on_hover_text(url).clicked() {
+                    *action = Some(MarkdownAction::Open(url.clone()));
+                }
+            }
+            Inline::Break => ui.end_row(),
+        }
+    }
+}
+
+fn line_ui(ui: &mut egui::Ui, line: &Line, editable: bool, action: &mut Option<MarkdownAction>) {
+    let size = line.heading.map_or(TEXT_SIZE, heading_size);
+    if line.heading.is_some() {
+        ui.add_space(6.0);
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        ui.add_space(line.depth as f32 * INDENT);
+        if line.quote {
+            ui.label(RichText::new("▍ ").size(size).color(Color32::GRAY));
+        }
+        match line.marker {
+            Some(Marker::Bullet) => {
+                ui.label(RichText::new("•  ").size(size));
+            }
+            Some(Marker::Number(number)) => {
+                ui.label(RichText::new(format!("{number}.  ")).size(size));
+            }
+            Some(Marker::Task { checked, offset }) => {
+                let mut value = checked;
+                if ui.add_enabled(editable, egui::Checkbox::without_text(&mut value)).changed() {
+                    *action = Some(MarkdownAction::Toggle(offset));
+                }
+                ui.add_space(4.0);
+            }
+            None => {}
+        }
+        inlines_ui(ui, &line.inlines, size, line.heading.is_some(), action);
+    });
+    if line.heading.is_some() {
+        ui.add_space(2.0);
+    }
+}
+
+/// Affiche `source` et renvoie le clic éventuel sur une case ou un lien.
+/// Les cases ne sont modifiables que si `editable`.
+fn markdown_ui(ui: &mut egui::Ui, blocks: &[Block], editable: bool) -> Option<MarkdownAction> {
+    let mut action = None;
+    for (index, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Line(line) => line_ui(ui, line, editable, &mut action),
+            Block::Code { depth, text } => {
+                ui.horizontal(|ui| {
+                    ui.add_space(*depth as f32 * INDENT);
+                    egui::Frame::group(ui.style()).fill(ui.visuals().extreme_bg_color).show(ui, |ui| {
+                        ui.label(RichText::new(text).size(13.0).monospace());
+                    });
+                });
+            }
+            Block::Table(rows) => {
+                egui::Grid::new(("markdown_table", index)).striped(true).show(ui, |ui| {
+                    for (row_index, row) in rows.iter().enumerate() {
+                        for cell in row {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.spacing_mut().item_spacing.x = 0.0;
+                                inlines_ui(ui, cell, TEXT_SIZE, row_index == 0, &mut action);
+                            });
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+            Block::Rule => {
+                ui.separator();
+            }
+        }
+    }
+    action
+}
+
+impl Glob1Env {
+    /// Rendu d'un fichier `.md` ; cocher une case réécrit le fichier
+    pub fn markdown_view(&mut self, ui: &mut egui::Ui, path: &Path, file: &File) {
+        let stale = self.markdown_preview.as_ref()
+            .is_none_or(|p| p.path != path || p.size != file.content.len() || p.modified != file.metadata.modified);
+        if stale {
+            self.markdown_preview = Some(MarkdownPreview {
+                path: path.to_path_buf(),
+                size: file.content.len(),
+                modified: file.metadata.modified,
+                blocks: parse(&file.content).into(),
+            });
+        }
+        let Some(blocks) = self.markdown_preview.as_ref().map(|p| p.blocks.clone()) else {
+            return;
+        };
+
+        let editable = !file.system && !file.metadata.readonly;
+        match markdown_ui(ui, &blocks, editable) {
+            Some(MarkdownAction::Toggle(offset)) => {
+                let result = toggle_task(&file.content, offset)
+                    .ok_or_else(|| "Case à cocher introuvable".to_string())
+                    .and_then(|content| self.wr(path, content, Origin::Editor));
+                self.explorer_error = result.err();
+            }
+            Some(MarkdownAction::Open(url)) if is_external(&url) => ui.ctx().open_url(egui::OpenUrl::new_tab(url)),
+            Some(MarkdownAction::Open(url)) => self.follow_link(path, &url),
+            None => {}
+        }
+    }
+
+    /// Ouvre la cible d'un lien relatif au fichier `from`, ou absolu dans le Glob1Env
+    fn follow_link(&mut self, from: &Path, url: &str) {
+        let target = url.split('#').next().unwrap_or_default();
+        if target.is_empty() {
+            return;
+        }
+        let dir = VPath::from(from.parent().unwrap_or(Path::new("/")));
+        let path = dir.resolve(&percent_decode(target));
+        if self.exists(&path) || self.mount_target(&path).is_some() {
+            self.open(&path);
+        } else {
+            self.explorer_error = Some(format!("Lien introuvable : '{}'", path.display()));
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use crate::binary::FileKind;
+use crate::fstree::{Node, NodeId};
+use crate::glob1env::Glob1Env;
+use crate::mount::{Backend, HostDir};
+use crate::undo::Mutation;
+
+/// Dates et attributs d'une entrée ; absents des sauvegardes antérieures
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    /// Refuse l'écriture, le déplacement, le renommage et la suppression
+    pub readonly: bool,
+    /// Masqué dans l'explorateur et `ls` sans `-a`
+    pub hidden: bool,
+    pub executable: bool,
+    /// Emplacement d'origine d'une entrée de la corbeille
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trashed_from: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flag {
+    ReadOnly,
+    Hidden,
+    Executable,
+}
+
+impl Metadata {
+    /// Entrée créée maintenant
+    pub fn now() -> Self {
+        let now = Some(Utc::now());
+        Self { created: now, modified: now, accessed: now, ..Default::default() }
+    }
+
+    pub fn flag(&self, flag: Flag) -> bool {
+        match flag {
+            Flag::ReadOnly => self.readonly,
+            Flag::Hidden => self.hidden,
+            Flag::Executable => self.executable,
+        }
+    }
+
+    pub fn set_flag(&mut self, flag: Flag, value: bool) {
+        match flag {
+            Flag::ReadOnly => self.readonly = value,
+            Flag::Hidden => self.hidden = value,
+            Flag::Executable => self.executable = value,
+        }
+    }
+
+    /// `rhxs`, un `-` pour chaque attribut absent
+    pub fn flags(&self, system: bool) -> String {
+        [(self.readonly, 'r'), (self.hidden, 'h'), (self.executable, 'x'), (system, 's')]
+            .iter()
+            .map(|&(set, c)| if set { c } else { '-' })
+            .collect()
+    }
+}
+
+impl Flag {
+    pub const ALL: [Flag; 3] = [Flag::ReadOnly, Flag::Hidden, Flag::Executable];
+
+    pub fn from_char(c: char) -> Result<Self, String> {
+        match c {
+            'r' => Ok(Flag::ReadOnly),
+            'h' => Ok(Flag::Hidden),
+            'x' => Ok(Flag::Executable),
+            other => Err(format!("Attribut inconnu : '{}' (r, h ou x)", other)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Flag::ReadOnly => "Read-only",
+            Flag::Hidden => "Hidden",
+            Flag::Executable => "Executable",
+        }
+    }
+}
+
+/// `+rh`, `-x` : attributs à ajouter ou retirer
+pub fn parse_mode(mode: &str) -> Result<(Vec<Flag>, bool), String> {
+    let (value, letters) = match mode.split_at_checked(1) {
+        Some(("+", letters)) => (true, letters),
+        Some(("-", letters)) => (false, letters),
+        _ => return Err(format!("Mode invalide : '{}' (ex. +r, -hx)", mode)),
+    };
+    if letters.is_empty() {
+        return Err(format!("Mode invalide : '{}' (ex. +r, -hx)", mode));
+    }
+    let flags = letters.chars().map(Flag::from_char).collect::<Result<_, _>>()?;
+    Ok((flags, value))
+}
+
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+pub fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl Glob1Env {
+    /// Taille stockée en octets ; celle d'un répertoire est la somme de son contenu
+    /// Un fichier monté est mesuré sur l'hôte
+    pub fn size(&self, id: NodeId) -> usize {
+        self.tree.descendants(id).into_iter()
+            .filter_map(|id| self.tree.node(id).as_file().map(|f| (id, f)))
+            .map(|(id, f)| match self.mount_of(id) {
+                Some((mount, rel)) => HostDir::open(&mount).and_then(|host| host.stat(&rel)).map_or(0, |e| e.size as usize),
+                None => f.data.as_ref().map_or(f.content.len(), Vec::len),
+            })
+            .sum()
+    }
+
+    pub fn stat(&self, path: &Path) -> Result<Vec<String>, String> {
+        let id = self.lookup(path).ok_or_else(|| format!("Introuvable : '{}'", path.display()))?;
+        let node = self.tree.node(id);
+        let metadata = node.metadata();
+        let kind = match node {
+            Node::File(f) if f.data.is_some() => format!("file ({})", FileKind::of(f).label()),
+            Node::File(_) => "file".to_string(),
+            Node::Directory(_) => "directory".to_string(),
+        };
+        let flags: Vec<&str> = Flag::ALL.iter()
+            .filter(|&&flag| metadata.flag(flag))
+            .map(|flag| flag.label())
+            .chain(node.is_system().then_some("System"))
+            .collect();
+        let size = self.size(id);
+
+        Ok(vec![
+            format!("  Path: {}", self.tree.path(id).display()),
+            format!("  Type: {}", kind),
+            format!("  Size: {} ({} bytes)", format_size(size), size),
+            format!("  Created: {}", format_time(metadata.created)),
+            format!("  Modified: {}", format_time(metadata.modified)),
+            format!("  Accessed: {}", format_time(metadata.accessed)),
+            match flags.is_empty() {
+                true => format!("  Flags: {}", metadata.flags(node.is_system())),
+                false => format!("  Flags: {} ({})", metadata.flags(node.is_system()), flags.join(", ")),
+            },
+        ])
+    }
+
+    /// Modifie des attributs ; une entrée système garde les siens
+    pub fn chmod(&mut self, path: &Path, flags: &[Flag], value: bool) -> Result<(), String> {
+        let id = self.lookup(path).ok_or_else(|| format!("Introuvable : '{}'", path.display()))?;
+        if self.tree.node(id).is_system() {
+            return Err(format!("Entrée système protégée : '{}'", path.display()));
+        }
+        let previous = self.tree.node(id).metadata().clone();
+        let metadata = self.tree.node_mut(id).metadata_mut();
+        for &flag in flags {
+            metadata.set_flag(flag, value);
+        }
+        self.record(Mutation::Flags { path: self.tree.path(id), previous });
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Note la lecture d'un fichier par l'utilisateur. Elle ne déclenche pas de sauvegarde :
+    /// la date part avec la prochaine modification.
+    pub fn mark_accessed(&mut self, path: &Path) {
+        if let Some(id) = self.lookup(path) {
+            self.tree.node_mut(id).metadata_mut().accessed = Some(Utc::now());
+        }
+    }
+
+    /// Refuse l'écriture dans un répertoire monté, en lecture seule ou sous un tel répertoire
+    pub fn writable_dir(&self, dir: NodeId) -> Result<(), String> {
+        self.no_readonly_ancestor(dir)?;
+        if self.mount_of(dir).is_some() {
+            return Err(format!("Répertoire monté : '{}' (seuls rd et wr sont pris en charge)", self.tree.path(dir).display()));
+        }
+        Ok(())
+    }
+
+    /// Refuse `dir` s'il est en lecture seule, lui ou l'un de ses parents
+    pub fn no_readonly_ancestor(&self, dir: NodeId) -> Result<(), String> {
+        let mut current = Some(dir);
+        while let Some(id) = current {
+            if self.tree.node(id).metadata().readonly {
+                return Err(format!("Répertoire en lecture seule : '{}'", self.tree.path(id).display()));
+            }
+            current = self.tree.parent(id);
+        }
+        Ok(())
+    }
+
+    /// Date de modification de `id`, à appeler après chaque changement de son contenu
+    pub fn mark_modified(&mut self, id: NodeId) {
+        let now = Some(Utc::now());
+        let metadata = self.tree.node_mut(id).metadata_mut();
+        metadata.modified = now;
+        metadata.accessed = now;
+    }
+}
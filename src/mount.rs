@@ -0,0 +1,386 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::binary::split_content;
+use crate::fstree::{Node, NodeId, ROOT};
+use crate::glob1env::{split_components, Directory, File, FsEntry, Glob1Env};
+use crate::metadata::Metadata;
+
+/// Répertoire de l'hôte monté sur un répertoire du Glob1Env.
+/// Seul le point de montage est sauvegardé, son contenu est relu sur l'hôte.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mount {
+    pub host: PathBuf,
+    #[serde(default)]
+    pub readonly: bool,
+}
+
+/// Entrée listée par un backend
+#[derive(Clone, Debug)]
+pub struct BackendEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub readonly: bool,
+}
+
+/// Stockage d'un sous-arbre monté ; les chemins sont relatifs au point de montage
+pub trait Backend {
+    fn list(&self, rel: &Path) -> Result<Vec<BackendEntry>, String>;
+    fn stat(&self, rel: &Path) -> Result<BackendEntry, String>;
+    fn read(&self, rel: &Path) -> Result<String, String>;
+    fn read_bytes(&self, rel: &Path) -> Result<Vec<u8>, String>;
+    fn write(&self, rel: &Path, content: &str) -> Result<(), String>;
+}
+
+/// Répertoire de l'hôte ; `..` et les liens symboliques ne peuvent pas en sortir
+pub struct HostDir {
+    root: PathBuf,
+    readonly: bool,
+}
+
+impl HostDir {
+    pub fn open(mount: &Mount) -> Result<Self, String> {
+        let root = fs::canonicalize(&mount.host)
+            .map_err(|e| format!("Impossible d'ouvrir '{}' : {}", mount.host.display(), e))?;
+        if !root.is_dir() {
+            return Err(format!("'{}' n'est pas un répertoire", mount.host.display()));
+        }
+        Ok(Self { root, readonly: mount.readonly })
+    }
+
+    /// Chemin de l'hôte pour `rel`, refusé s'il sort de la racine une fois les liens résolus
+    fn resolve(&self, rel: &Path) -> Result<PathBuf, String> {
+        let escape = || format!("Chemin hors du montage : '{}'", rel.display());
+        let mut path = self.root.clone();
+        for component in rel.components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::CurDir => {}
+                _ => return Err(escape()),
+            }
+        }
+
+        // Un fichier encore inexistant est vérifié par son répertoire
+        let existing = match fs::canonicalize(&path) {
+            Ok(real) => real,
+            Err(_) if path.symlink_metadata().is_ok() => return Err(escape()),
+            Err(_) => {
+                let parent = path.parent().ok_or_else(escape)?;
+                fs::canonicalize(parent).map_err(|e| format!("'{}' : {}", rel.display(), e))?
+            }
+        };
+        if !existing.starts_with(&self.root) {
+            return Err(escape());
+        }
+        Ok(path)
+    }
+
+    fn entry(&self, name: String, path: &Path) -> Result<BackendEntry, String> {
+        let metadata = fs::metadata(path).map_err(|e| format!("'{}' : {}", path.display(), e))?;
+        Ok(BackendEntry {
+            name,
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            readonly: self.readonly || metadata.permissions().readonly(),
+        })
+    }
+}
+
+impl Backend for HostDir {
+    fn list(&self, rel: &Path) -> Result<Vec<BackendEntry>, String> {
+        let dir = self.resolve(rel)?;
+        let mut entries = Vec::new();
+        for item in fs::read_dir(&dir).map_err(|e| format!("'{}' : {}", rel.display(), e))? {
+            let Ok(item) = item else { continue };
+            let Ok(name) = item.file_name().into_string() else { continue };
+            // Les liens qui sortent du montage et les entrées illisibles sont ignorés
+            if let Ok(path) = self.resolve(&rel.join(&name))
+                && let Ok(entry) = self.entry(name, &path)
+            {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+
+    fn stat(&self, rel: &Path) -> Result<BackendEntry, String> {
+        let name = rel.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        self.entry(name, &self.resolve(rel)?)
+    }
+
+    fn read(&self, rel: &Path) -> Result<String, String> {
+        fs::read_to_string(self.resolve(rel)?).map_err(|e| format!("'{}' : {}", rel.display(), e))
+    }
+
+    fn read_bytes(&self, rel: &Path) -> Result<Vec<u8>, String> {
+        fs::read(self.resolve(rel)?).map_err(|e| format!("'{}' : {}", rel.display(), e))
+    }
+
+    fn write(&self, rel: &Path, content: &str) -> Result<(), String> {
+        if self.readonly {
+            return Err(format!("Montage en lecture seule : '{}'", rel.display()));
+        }
+        fs::write(self.resolve(rel)?, content).map_err(|e| format!("'{}' : {}", rel.display(), e))
+    }
+}
+
+impl Glob1Env {
+    /// Monte le répertoire `host` de l'hôte sur `at`, qui ne doit pas exister
+    pub fn mount(&mut self, host: &Path, at: &Path, readonly: bool) -> Result<(), String> {
+        let mount = Mount { host: host.to_path_buf(), readonly };
+        let host_dir = HostDir::open(&mount)?;
+        self.insert_entry(at, FsEntry::Directory(Directory {
+            name: String::new(),
+            content: Vec::new(),
+            system: false,
+            metadata: Metadata::now(),
+            mount: Some(Mount { host: host_dir.root, readonly }),
+        }))?;
+        self.sync_path(at)
+    }
+
+    pub fn umount(&mut self, at: &Path) -> Result<(), String> {
+        let id = self.lookup(at)
+            .filter(|&id| self.tree.node(id).as_dir().is_some_and(|d| d.mount.is_some()))
+            .ok_or_else(|| format!("Pas un point de montage : '{}'", at.display()))?;
+        self.tree.remove(id);
+        self.moved(at, None);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Points de montage, avec leur répertoire de l'hôte
+    pub fn mounts(&self) -> Vec<(PathBuf, Mount)> {
+        self.tree.descendants(ROOT).into_iter()
+            .filter_map(|id| Some((self.tree.path(id), self.tree.node(id).as_dir()?.mount.clone()?)))
+            .collect()
+    }
+
+    /// Montage qui contient `id` et chemin relatif de `id` dans ce montage (vide pour le point de montage)
+    pub fn mount_of(&self, id: NodeId) -> Option<(Mount, PathBuf)> {
+        let mut names = Vec::new();
+        let mut current = id;
+        loop {
+            if let Some(mount) = self.tree.node(current).as_dir().and_then(|d| d.mount.clone()) {
+                return Some((mount, names.into_iter().rev().collect()));
+            }
+            names.push(self.tree.node(current).name().to_string());
+            current = self.tree.parent(current)?;
+        }
+    }
+
+    /// Comme `mount_of`, pour un chemin dont la fin n'a pas encore été lue sur l'hôte
+    pub fn mount_target(&self, path: &Path) -> Option<(Mount, PathBuf)> {
+        let parts = split_components(path);
+        let mut id = ROOT;
+        for (i, name) in parts.iter().enumerate() {
+            if let Some((mount, rel)) = self.mount_of(id) {
+                return Some((mount, rel.join(parts[i..].iter().collect::<PathBuf>())));
+            }
+            id = self.tree.child(id, name)?;
+        }
+        self.mount_of(id)
+    }
+
+    /// Relit sur l'hôte les répertoires montés traversés par `path`
+    pub fn sync_path(&mut self, path: &Path) -> Result<(), String> {
+        let parts: Vec<String> = split_components(path).into_iter().map(String::from).collect();
+        let mut id = ROOT;
+        for name in &parts {
+            if self.tree.child(id, name).is_none() && self.mount_of(id).is_some() {
+                self.sync_dir(id)?;
+            }
+            match self.tree.child(id, name) {
+                Some(child) => id = child,
+                None => return Ok(()),
+            }
+        }
+        if self.tree.node(id).is_dir() && self.mount_of(id).is_some() {
+            self.sync_dir(id)?;
+        }
+        Ok(())
+    }
+
+    /// Accorde les enfants du répertoire monté `id` au listing de l'hôte : les entrées
+    /// toujours présentes gardent leur nœud et leur sous-arbre déjà lu, seules les
+    /// entrées apparues ou disparues sont ajoutées ou retirées
+    fn sync_dir(&mut self, id: NodeId) -> Result<(), String> {
+        let (mount, rel) = self.mount_of(id).ok_or("Pas dans un montage")?;
+        let entries = HostDir::open(&mount)?.list(&rel)?;
+
+        // Un fichier devenu répertoire (ou l'inverse) est remplacé
+        for child in self.tree.children(id).to_vec() {
+            let node = self.tree.node(child);
+            if !entries.iter().any(|entry| entry.name == node.name() && entry.is_dir == node.is_dir()) {
+                self.tree.remove(child);
+            }
+        }
+        for entry in entries {
+            if let Some(child) = self.tree.child(id, &entry.name) {
+                let metadata = self.tree.node(child).metadata();
+                if metadata.modified != entry.modified || metadata.readonly != entry.readonly {
+                    let metadata = self.tree.node_mut(child).metadata_mut();
+                    metadata.modified = entry.modified;
+                    metadata.readonly = entry.readonly;
+                }
+                continue;
+            }
+            let metadata = Metadata { modified: entry.modified, readonly: entry.readonly, ..Default::default() };
+            let node = if entry.is_dir {
+                FsEntry::Directory(Directory { name: entry.name, content: Vec::new(), system: false, metadata, mount: None })
+            } else {
+                FsEntry::File(File { name: entry.name, content: String::new(), system: false, metadata, history: Vec::new(), data: None })
+            };
+            // Un nom invalide dans le Glob1Env (`.`, `..`) n'est pas listé
+            let _ = self.tree.insert(id, node);
+        }
+        if let Some(selected) = self.selected_file.clone()
+            && selected.starts_with(self.tree.path(id))
+        {
+            self.load_mounted(&selected);
+        }
+        Ok(())
+    }
+
+    /// Copie dans l'arborescence le contenu actuel d'un fichier monté, pour l'affichage
+    pub fn load_mounted(&mut self, path: &Path) {
+        let Some(id) = self.lookup(path).filter(|&id| !self.tree.node(id).is_dir()) else {
+            return;
+        };
+        let Some((mount, rel)) = self.mount_of(id) else {
+            return;
+        };
+        let (content, data) = match HostDir::open(&mount).and_then(|host| host.read_bytes(&rel)) {
+            Ok(bytes) => split_content(bytes),
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        if let Node::File(file) = self.tree.node_mut(id) {
+            file.content = content;
+            file.data = data;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Origin;
+    use crate::vault::Vault;
+
+    /// Montage de `<tmp>/root` ; `<tmp>/outside` est à côté, hors du montage
+    fn host() -> (tempfile::TempDir, HostDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("root/sub")).unwrap();
+        fs::create_dir(tmp.path().join("outside")).unwrap();
+        fs::write(tmp.path().join("root/sub/f.txt"), "inside").unwrap();
+        fs::write(tmp.path().join("outside/secret.txt"), "outside").unwrap();
+        let host = HostDir::open(&Mount { host: tmp.path().join("root"), readonly: false }).unwrap();
+        (tmp, host)
+    }
+
+    #[test]
+    fn resolve_stays_in_root() {
+        let (_tmp, host) = host();
+        assert_eq!(host.resolve(Path::new("sub/f.txt")).unwrap(), host.root.join("sub/f.txt"));
+        assert_eq!(host.resolve(Path::new("./sub/./f.txt")).unwrap(), host.root.join("sub/f.txt"));
+        assert_eq!(host.resolve(Path::new("")).unwrap(), host.root);
+        // Un fichier à créer est vérifié par son répertoire
+        assert_eq!(host.resolve(Path::new("sub/new.txt")).unwrap(), host.root.join("sub/new.txt"));
+    }
+
+    #[test]
+    fn resolve_rejects_dotdot_and_absolute() {
+        let (_tmp, host) = host();
+        assert!(host.resolve(Path::new("../outside/secret.txt")).is_err());
+        assert!(host.resolve(Path::new("sub/../../outside")).is_err());
+        assert!(host.resolve(Path::new("sub/..")).is_err());
+        assert!(host.resolve(Path::new("/etc/passwd")).is_err());
+        assert!(host.read(Path::new("../outside/secret.txt")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_rejects_symlinks_out_of_root() {
+        use std::os::unix::fs::symlink;
+        let (tmp, host) = host();
+        symlink(tmp.path().join("outside"), host.root.join("out")).unwrap();
+        symlink(tmp.path().join("outside/secret.txt"), host.root.join("secret.txt")).unwrap();
+        symlink(tmp.path().join("missing"), host.root.join("dangling")).unwrap();
+        symlink(host.root.join("sub/f.txt"), host.root.join("link.txt")).unwrap();
+
+        assert!(host.resolve(Path::new("out")).is_err());
+        assert!(host.resolve(Path::new("out/secret.txt")).is_err());
+        assert!(host.resolve(Path::new("out/new.txt")).is_err());
+        assert!(host.resolve(Path::new("secret.txt")).is_err());
+        assert!(host.resolve(Path::new("dangling")).is_err());
+        assert!(host.write(Path::new("out/new.txt"), "x").is_err());
+        assert!(!tmp.path().join("outside/new.txt").exists());
+
+        // Un lien qui reste dans le montage est suivi
+        assert_eq!(host.read(Path::new("link.txt")).unwrap(), "inside");
+        let names: Vec<String> = host.list(Path::new("")).unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["sub", "link.txt"]);
+    }
+
+    #[test]
+    fn readonly_mount_refuses_writes() {
+        let (tmp, _) = host();
+        let host = HostDir::open(&Mount { host: tmp.path().join("root"), readonly: true }).unwrap();
+        assert!(host.write(Path::new("sub/f.txt"), "x").is_err());
+        assert_eq!(fs::read_to_string(tmp.path().join("root/sub/f.txt")).unwrap(), "inside");
+    }
+
+    #[test]
+    fn readonly_virtual_ancestor_protects_mount() {
+        let (tmp, host) = host();
+        let mut env = Glob1Env::new(None, Vault::default());
+        env.mkdir(Path::new("/v")).unwrap();
+        env.mount(&host.root, Path::new("/v/m"), false).unwrap();
+        let id = env.lookup(Path::new("/v")).unwrap();
+        env.tree.node_mut(id).metadata_mut().readonly = true;
+
+        assert!(env.wr(Path::new("/v/m/sub/f.txt"), "x".to_string(), Origin::Terminal).is_err());
+        assert_eq!(fs::read_to_string(tmp.path().join("root/sub/f.txt")).unwrap(), "inside");
+
+        env.tree.node_mut(id).metadata_mut().readonly = false;
+        env.wr(Path::new("/v/m/sub/f.txt"), "x".to_string(), Origin::Terminal).unwrap();
+        assert_eq!(fs::read_to_string(tmp.path().join("root/sub/f.txt")).unwrap(), "x");
+    }
+
+    #[test]
+    fn sync_keeps_subtrees_and_reloads_selection() {
+        let (_tmp, host) = host();
+        fs::create_dir(host.root.join("sub/deep")).unwrap();
+        fs::write(host.root.join("sub/deep/g.txt"), "v1").unwrap();
+        let mut env = Glob1Env::new(None, Vault::default());
+        env.mount(&host.root, Path::new("/m"), false).unwrap();
+        env.sync_path(Path::new("/m/sub/deep")).unwrap();
+        env.select("/m/sub/deep/g.txt".into());
+        let deep = env.lookup(Path::new("/m/sub/deep")).unwrap();
+        assert_eq!(env.file(Path::new("/m/sub/deep/g.txt")).unwrap().content, "v1");
+
+        // Un changement dans /m/sub ne vide pas /m/sub/deep et relit le fichier sélectionné
+        fs::write(host.root.join("sub/new.txt"), "").unwrap();
+        fs::write(host.root.join("sub/deep/g.txt"), "v2").unwrap();
+        fs::remove_file(host.root.join("sub/f.txt")).unwrap();
+        env.sync_path(Path::new("/m/sub")).unwrap();
+        assert_eq!(env.lookup(Path::new("/m/sub/deep")), Some(deep));
+        assert_eq!(env.file(Path::new("/m/sub/deep/g.txt")).unwrap().content, "v2");
+        assert!(env.exists(Path::new("/m/sub/new.txt")));
+        assert!(!env.exists(Path::new("/m/sub/f.txt")));
+
+        // Une entrée qui change de type est remplacée
+        fs::remove_dir_all(host.root.join("sub/deep")).unwrap();
+        fs::write(host.root.join("sub/deep"), "now a file").unwrap();
+        env.sync_path(Path::new("/m/sub")).unwrap();
+        assert!(!env.is_dir(Path::new("/m/sub/deep")));
+    }
+}
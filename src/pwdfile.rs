@@ -0,0 +1,338 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// Version courante du format des fichiers `.pwd`
+pub const PWD_VERSION: u32 = 1;
+
+const HEADER_PREFIX: &str = "#pwd v";
+const ENTRY_START: &str = "[ENTRY]";
+const ENTRY_END: &str = "[END]";
+
+/// Un identifiant stocké dans un fichier `.pwd`.
+///
+/// Format v1 :
+/// ```text
+/// #pwd v1
+/// [ENTRY]
+/// service: Google
+/// username: me@example.com
+/// secret: s3cr::et
+/// url: https://accounts.google.com
+/// tags: mail, perso
+/// notes: première ligne\nseconde ligne
+/// created: 2026-10-18T10:00:00Z
+/// updated: 2026-10-18T10:00:00Z
+/// [END]
+/// ```
+/// Les anciennes lignes `[PWD] service::secret` restent lues comme des identifiants.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Credential {
+    pub service: String,
+    pub username: String,
+    pub secret: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    pub notes: String,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    /// Ligne (1-based) où commence l'identifiant dans le fichier
+    pub line: usize,
+    pub legacy: bool,
+    /// Ligne `[PWD]` d'origine d'un identifiant `legacy`, réécrite telle quelle
+    pub raw: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum PwdItem {
+    Credential(Credential),
+    /// Ligne `[TOTP] ...`, interprétée par `totp::Totp::parse_line`
+    Totp(String),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PwdFile {
+    pub version: u32,
+    pub items: Vec<PwdItem>,
+    pub errors: Vec<ParseError>,
+}
+
+impl PwdFile {
+    pub fn credentials(&self) -> impl Iterator<Item = &Credential> {
+        self.items.iter().filter_map(|item| match item {
+            PwdItem::Credential(credential) => Some(credential),
+            _ => None,
+        })
+    }
+
+    pub fn credentials_mut(&mut self) -> impl Iterator<Item = &mut Credential> {
+        self.items.iter_mut().filter_map(|item| match item {
+            PwdItem::Credential(credential) => Some(credential),
+            _ => None,
+        })
+    }
+
+    /// Réécrit le fichier au format courant ; les lignes `[PWD]` d'origine sont conservées telles quelles
+    pub fn to_content(&self) -> String {
+        let mut lines = vec![format!("{HEADER_PREFIX}{PWD_VERSION}")];
+        for item in &self.items {
+            lines.push(match item {
+                PwdItem::Credential(credential) if credential.legacy => credential.raw.clone(),
+                PwdItem::Credential(credential) => credential.to_record(),
+                PwdItem::Totp(line) | PwdItem::Text(line) => line.clone(),
+            });
+        }
+        lines.join("\n")
+    }
+}
+
+impl Credential {
+    pub fn new(service: &str, secret: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            service: service.to_string(),
+            secret: secret.to_string(),
+            created: Some(now),
+            updated: Some(now),
+            ..Default::default()
+        }
+    }
+
+    /// Bloc `[ENTRY] ... [END]` au format courant ; les champs vides sont omis
+    pub fn to_record(&self) -> String {
+        let mut lines = vec![ENTRY_START.to_string()];
+        let mut field = |key: &str, value: &str| {
+            if !value.is_empty() {
+                lines.push(format!("{key}: {}", escape(value)));
+            }
+        };
+        field("service", &self.service);
+        field("username", &self.username);
+        field("secret", &self.secret);
+        field("url", &self.url);
+        field("tags", &self.tags.join(", "));
+        field("notes", &self.notes);
+        let timestamp = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)).unwrap_or_default();
+        field("created", &timestamp(self.created));
+        field("updated", &timestamp(self.updated));
+        lines.push(ENTRY_END.to_string());
+        lines.join("\n")
+    }
+}
+
+/// `[PWD] service::secret` -> (service, secret) ; le secret est gardé tel quel
+pub fn parse_pwd_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("[PWD]")?;
+    let (service, secret) = rest.split_once("::").unwrap_or((rest, ""));
+    Some((service.trim(), secret))
+}
+
+pub fn parse(content: &str) -> PwdFile {
+    let mut file = PwdFile { version: PWD_VERSION, ..Default::default() };
+    let mut current: Option<Credential> = None;
+
+    for (index, raw) in content.lines().enumerate() {
+        let line_no = index + 1;
+        // Les marqueurs sont reconnus sans les blancs, les valeurs gardent les leurs
+        let line = raw.trim();
+
+        if let Some(credential) = current.as_mut() {
+            if line == ENTRY_END {
+                let credential = current.take().unwrap();
+                if credential.service.is_empty() {
+                    file.errors.push(ParseError { line: credential.line, message: "champ 'service' manquant".into() });
+                }
+                file.items.push(PwdItem::Credential(credential));
+            } else if line == ENTRY_START {
+                file.errors.push(ParseError { line: credential.line, message: format!("{ENTRY_START} sans {ENTRY_END}") });
+                current = Some(Credential { line: line_no, ..Default::default() });
+            } else if !line.is_empty()
+                && let Err(message) = set_field(credential, raw.trim_start())
+            {
+                file.errors.push(ParseError { line: line_no, message });
+            }
+            continue;
+        }
+
+        if let Some(version) = line.strip_prefix(HEADER_PREFIX) {
+            match version.trim().parse::<u32>() {
+                Ok(version) if version <= PWD_VERSION => file.version = version,
+                Ok(version) => file.errors.push(ParseError {
+                    line: line_no,
+                    message: format!("version {} non supportée (max {})", version, PWD_VERSION),
+                }),
+                Err(_) => file.errors.push(ParseError { line: line_no, message: format!("en-tête invalide : '{}'", line) }),
+            }
+        } else if line == ENTRY_START {
+            current = Some(Credential { line: line_no, ..Default::default() });
+        } else if line == ENTRY_END {
+            file.errors.push(ParseError { line: line_no, message: format!("{ENTRY_END} sans {ENTRY_START}") });
+        } else if let Some((service, secret)) = parse_pwd_line(raw.trim_start()) {
+            file.items.push(PwdItem::Credential(Credential {
+                service: service.to_string(),
+                secret: secret.to_string(),
+                line: line_no,
+                legacy: true,
+                raw: raw.to_string(),
+                ..Default::default()
+            }));
+        } else if line.starts_with("[TOTP]") {
+            file.items.push(PwdItem::Totp(line.to_string()));
+        } else {
+            file.items.push(PwdItem::Text(raw.to_string()));
+        }
+    }
+
+    if let Some(credential) = current {
+        file.errors.push(ParseError { line: credential.line, message: format!("{ENTRY_START} sans {ENTRY_END}") });
+        file.items.push(PwdItem::Credential(credential));
+    }
+
+    file
+}
+
+fn set_field(credential: &mut Credential, line: &str) -> Result<(), String> {
+    let (key, value) = line.split_once(':').ok_or_else(|| format!("ligne invalide : '{}'", line))?;
+    let value = unescape(value.strip_prefix(' ').unwrap_or(value));
+
+    match key.trim() {
+        "service" => credential.service = value,
+        "username" => credential.username = value,
+        "secret" => credential.secret = value,
+        "url" => credential.url = value,
+        "tags" => credential.tags = value.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        "notes" => credential.notes = value,
+        "created" => credential.created = Some(parse_timestamp(&value)?),
+        "updated" => credential.updated = Some(parse_timestamp(&value)?),
+        other => return Err(format!("champ inconnu : '{}'", other)),
+    }
+    Ok(())
+}
+
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| format!("date invalide : '{}'", value))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Ajoute un identifiant à la fin d'un contenu `.pwd`, en posant l'en-tête si besoin
+pub fn append_credential(content: &str, credential: &Credential) -> String {
+    let mut out = content.trim_end().to_string();
+    if !out.lines().any(|line| line.trim().starts_with(HEADER_PREFIX)) {
+        out = if out.is_empty() {
+            format!("{HEADER_PREFIX}{PWD_VERSION}")
+        } else {
+            format!("{HEADER_PREFIX}{PWD_VERSION}\n{out}")
+        };
+    }
+    out.push('\n');
+    out.push_str(&credential.to_record());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(content: &str) -> Credential {
+        let file = parse(content);
+        assert_eq!(file.errors, []);
+        file.credentials().next().unwrap().clone()
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        for value in ["plain", " padded ", "a\\b", "a\\nb", "line\nbreak", "cr\r\nlf", "trailing\\", "s3cr::et", "\\\\n"] {
+            assert_eq!(unescape(&escape(value)), value);
+        }
+        assert_eq!(escape("a\\b\nc\rd"), "a\\\\b\\nc\\rd");
+        assert_eq!(unescape("a\\qb"), "aqb");
+        assert_eq!(unescape("end\\"), "end\\");
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let mut original = Credential::new("  Mail ", " p@ss\\word\r\nwith: colons ");
+        original.username = "me@example.com".into();
+        original.url = "https://example.com".into();
+        original.tags = vec!["mail".into(), "perso".into()];
+        original.notes = "first line\nsecond line\\n".into();
+        original.created = Some(parse_timestamp("2026-10-18T10:00:00Z").unwrap());
+        original.updated = original.created;
+
+        let parsed = credential(&original.to_record());
+        assert_eq!(parsed, Credential { line: 1, ..original });
+    }
+
+    #[test]
+    fn content_round_trip() {
+        let content = "#pwd v1\n  [PWD]  old service :: x \n[ENTRY]\nservice: a\nsecret: b\\nc\n[END]\n[TOTP] a::JBSWY3DPEHPK3PXP\nfree text ";
+        let file = parse(content);
+        assert_eq!(file.errors, []);
+        assert_eq!(file.to_content(), content);
+
+        let legacy = file.credentials().next().unwrap();
+        assert!(legacy.legacy);
+        assert_eq!((legacy.service.as_str(), legacy.secret.as_str()), ("old service", " x "));
+        assert_eq!(file.credentials().nth(1).unwrap().secret, "b\nc");
+    }
+
+    #[test]
+    fn header_is_added_on_rewrite() {
+        assert_eq!(parse("[PWD] a::b").to_content(), "#pwd v1\n[PWD] a::b");
+        let appended = append_credential("[PWD] a::b\n", &Credential::new("c", "d"));
+        assert!(appended.starts_with("#pwd v1\n[PWD] a::b\n[ENTRY]\n"));
+        assert_eq!(parse(&appended).credentials().count(), 2);
+    }
+
+    #[test]
+    fn unterminated_blocks_are_errors() {
+        let file = parse("[ENTRY]\nservice: a\n[ENTRY]\nservice: b");
+        assert_eq!(file.errors, [
+            ParseError { line: 1, message: "[ENTRY] sans [END]".into() },
+            ParseError { line: 3, message: "[ENTRY] sans [END]".into() },
+        ]);
+        assert_eq!(file.credentials().map(|c| c.service.as_str()).collect::<Vec<_>>(), ["b"]);
+
+        let file = parse("[END]\n[ENTRY]\nsecret: x\nbogus line\n[END]");
+        assert_eq!(file.errors, [
+            ParseError { line: 1, message: "[END] sans [ENTRY]".into() },
+            ParseError { line: 4, message: "ligne invalide : 'bogus line'".into() },
+            ParseError { line: 2, message: "champ 'service' manquant".into() },
+        ]);
+    }
+
+    #[test]
+    fn unsupported_version_is_an_error() {
+        let file = parse("#pwd v2");
+        assert_eq!(file.errors[0].message, "version 2 non supportée (max 1)");
+        assert_eq!(parse("#pwd vx").errors[0].message, "en-tête invalide : '#pwd vx'");
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use regex::{Regex, RegexBuilder};
+use crate::fstree::Node;
+use crate::glob1env::{is_vault_file, Glob1Env};
+
+/// Nombre maximal de résultats renvoyés par une recherche
+pub const MAX_RESULTS: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    /// `*` et `?`, sur le nom ou la ligne entière
+    Glob,
+    Regex,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub mode: SearchMode,
+    pub case_sensitive: bool,
+    /// Cherche dans le contenu des fichiers plutôt que dans les noms
+    pub content: bool,
+    pub include_hidden: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    /// Ligne trouvée (numéro à partir de 1) pour une recherche dans le contenu.
+    /// Le texte des fichiers `.pwd` n'est pas repris.
+    pub line: Option<(usize, Option<String>)>,
+}
+
+/// Dernière recherche de l'explorateur, valable tant que la requête, le dossier
+/// et la génération de l'arborescence n'ont pas changé
+#[derive(Clone, Debug)]
+pub struct SearchCache {
+    pub query: SearchQuery,
+    pub scope: PathBuf,
+    pub generation: u64,
+    pub hits: Result<Vec<SearchHit>, String>,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 3] = [SearchMode::Substring, SearchMode::Glob, SearchMode::Regex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchMode::Substring => "text",
+            SearchMode::Glob => "glob",
+            SearchMode::Regex => "regex",
+        }
+    }
+}
+
+impl SearchQuery {
+    fn matcher(&self) -> Result<Regex, String> {
+        let pattern = match self.mode {
+            SearchMode::Substring => regex::escape(&self.pattern),
+            SearchMode::Glob => glob_to_regex(&self.pattern),
+            SearchMode::Regex => self.pattern.clone(),
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| format!("Expression invalide : {}", e))
+    }
+}
+
+impl SearchHit {
+    pub fn label(&self, base: &Path) -> String {
+        let path = self.path.strip_prefix(base)
+            .ok()
+            .filter(|rest| !rest.as_os_str().is_empty())
+            .unwrap_or(&self.path);
+        match &self.line {
+            None => path.display().to_string(),
+            Some((number, Some(text))) => format!("{}:{}: {}", path.display(), number, text.trim()),
+            Some((number, None)) => format!("{}:{}", path.display(), number),
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+impl Glob1Env {
+    /// Cherche `query` sous `scope`, lui exclu, dans l'ordre de l'arborescence.
+    /// Les fichiers `.pwd` ne sont fouillés que si le coffre est déverrouillé.
+    pub fn search(&self, scope: &Path, query: &SearchQuery) -> Result<Vec<SearchHit>, String> {
+        let scope = self.lookup(scope).ok_or_else(|| format!("Introuvable : '{}'", scope.display()))?;
+        let matcher = query.matcher()?;
+
+        let mut hits = Vec::new();
+        let mut stack: Vec<_> = self.tree.children(scope).iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = self.tree.node(id);
+            if node.metadata().hidden && !query.include_hidden {
+                continue;
+            }
+            stack.extend(self.tree.children(id).iter().rev());
+
+            if !query.content {
+                if matcher.is_match(node.name()) {
+                    hits.push(SearchHit { path: self.tree.path(id), line: None });
+                }
+            } else if let Node::File(file) = node {
+                let vault = is_vault_file(&file.name);
+                if vault && !self.vault.is_unlocked() {
+                    continue;
+                }
+                let plaintext = if vault { Some(self.vault.decrypt(&file.content)?) } else { None };
+                let content = plaintext.as_deref().unwrap_or(&file.content);
+                let path = self.tree.path(id);
+                for (i, line) in content.lines().enumerate().filter(|(_, line)| matcher.is_match(line)) {
+                    let text = (!vault).then(|| line.to_string());
+                    hits.push(SearchHit { path: path.clone(), line: Some((i + 1, text)) });
+                    if hits.len() >= MAX_RESULTS {
+                        break;
+                    }
+                }
+            }
+            if hits.len() >= MAX_RESULTS {
+                break;
+            }
+        }
+        Ok(hits)
+    }
+}
@@ -1,8 +1,21 @@
 use crate::Page;
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct Settings{
     //max_history_lines: usize,
+    /// Verrouillage automatique du coffre après inactivité, en minutes
+    pub auto_lock_minutes: u32,
+    /// Délai avant d'effacer un secret copié du presse-papiers, en secondes
+    pub clipboard_clear_secs: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_lock_minutes: 5,
+            clipboard_clear_secs: 20,
+        }
+    }
 }
 
 impl Settings {
@@ -27,6 +40,14 @@ impl Settings {
 
 
         ui.add_space(8.0);
+        ui.add(
+            egui::Slider::new(&mut self.auto_lock_minutes, 1..=60)
+                .text("Auto-lock (min)"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.clipboard_clear_secs, 5..=120)
+                .text("Clear clipboard (s)"),
+        );
         //ui.add(
         //    egui::Slider::new(&mut self.max_history_lines, 10..=500)
         //        .text("Max history lines"),
@@ -0,0 +1,178 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use serde_json::Value;
+use crate::glob1env::FsEntry;
+
+/// Version courante du format de sauvegarde de glob1env
+pub const FORMAT_VERSION: u64 = 1;
+
+const APP_DIR: &str = "nwtzui";
+const DATA_FILE: &str = "glob1env.json";
+const VAULT_FILE: &str = "vault.json";
+
+#[derive(Deserialize)]
+struct Snapshot {
+    #[allow(dead_code)]
+    version: u64,
+    root: Vec<FsEntry>,
+}
+
+/// `$XDG_DATA_HOME/nwtzui/glob1env.json` (ou l'équivalent de la plateforme)
+pub fn data_file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR).join(DATA_FILE))
+}
+
+/// En-tête du coffre (sel et paramètres de dérivation), à côté de `glob1env.json`
+pub fn vault_file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR).join(VAULT_FILE))
+}
+
+/// Charge l'arborescence depuis `path`. `Ok(None)` si le fichier n'existe pas encore.
+pub fn load(path: &Path) -> Result<Option<Vec<FsEntry>>, String> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Lecture de '{}' impossible : {}", path.display(), e)),
+    };
+
+    let value: Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Fichier '{}' corrompu : {}", path.display(), e))?;
+    let snapshot: Snapshot = serde_json::from_value(migrate(value)?)
+        .map_err(|e| format!("Fichier '{}' invalide : {}", path.display(), e))?;
+
+    Ok(Some(snapshot.root))
+}
+
+pub fn save(path: &Path, root: &[FsEntry]) -> Result<(), String> {
+    let snapshot = serde_json::json!({
+        "version": FORMAT_VERSION,
+        "root": root,
+    });
+    let data = serde_json::to_vec_pretty(&snapshot).map_err(|e| e.to_string())?;
+    write_atomic(path, &data)
+}
+
+/// Écrit dans un fichier temporaire puis le renomme sur `path`,
+/// pour ne jamais laisser de sauvegarde à moitié écrite.
+/// Le fichier temporaire est créé à côté, sous un nom unique : aucun fichier existant n'est écrasé.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    write_with(path, data, false)
+}
+
+/// Comme `write_atomic`, pour un fichier en clair que seul son propriétaire peut lire (0600 sous Unix)
+pub fn write_private(path: &Path, data: &[u8]) -> Result<(), String> {
+    write_with(path, data, true)
+}
+
+fn write_with(path: &Path, data: &[u8], private: bool) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Création de '{}' impossible : {}", parent.display(), e))?;
+    }
+
+    let error = |e: std::io::Error| format!("Écriture de '{}' impossible : {}", path.display(), e);
+    let (tmp, mut file) = create_temp(path, private).map_err(error)?;
+    let written = file.write_all(data).and_then(|()| file.sync_all());
+    drop(file);
+    written.and_then(|()| fs::rename(&tmp, path)).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        error(e)
+    })
+}
+
+/// Crée `.<nom>.<pid>-<aléa>.tmp` dans le répertoire de `path`, en refusant un fichier existant
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn create_temp(path: &Path, private: bool) -> std::io::Result<(PathBuf, fs::File)> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let mut attempts = 0;
+    loop {
+        let tmp = path.with_file_name(format!(".{}.{}-{:08x}.tmp", name, std::process::id(), rand::random::<u32>()));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&tmp) {
+            Ok(file) => return Ok((tmp, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempts < 16 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Amène un fichier de n'importe quelle version connue jusqu'à `FORMAT_VERSION`.
+/// Chaque nouvelle version ajoute une étape ici plutôt que de casser les anciens fichiers.
+fn migrate(mut value: Value) -> Result<Value, String> {
+    loop {
+        let version = match &value {
+            // v0 : tableau d'entrées brut, sans enveloppe versionnée
+            Value::Array(_) => 0,
+            Value::Object(map) => map.get("version").and_then(Value::as_u64).unwrap_or(0),
+            _ => return Err("Format de sauvegarde inconnu".into()),
+        };
+
+        value = match version {
+            0 => serde_json::json!({
+                "version": 1,
+                "root": match value {
+                    Value::Array(entries) => Value::Array(entries),
+                    Value::Object(mut map) => map.remove("root").unwrap_or(Value::Array(Vec::new())),
+                    _ => unreachable!(),
+                },
+            }),
+            FORMAT_VERSION => return Ok(value),
+            v => return Err(format!("Version de sauvegarde {} non supportée (max {})", v, FORMAT_VERSION)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_leaves_neighbours_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.csv");
+        fs::write(dir.path().join("report.tmp"), "unrelated").unwrap();
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(dir.path().join("report.tmp")).unwrap(), "unrelated");
+        assert_eq!(entries(dir.path()), ["report.csv", "report.tmp"]);
+    }
+
+    #[test]
+    fn failed_write_removes_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // Renommer un fichier sur un répertoire non vide échoue
+        let target = dir.path().join("busy");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("inside"), "").unwrap();
+
+        assert!(write_atomic(&target, b"data").is_err());
+        assert_eq!(entries(dir.path()), ["busy"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        write_private(&path, b"secret").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
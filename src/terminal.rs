@@ -1,14 +1,25 @@
 use std::collections::VecDeque;
-use std::path::Path;
-use std::process::exit;
+use std::path::{Path, PathBuf};
 use eframe::epaint::Stroke;
 use crate::{NwtzUi, Page, MAX_HISTORY_LINES, PROMPT};
+use crate::argv::split_args;
+use crate::commands::CommandContext;
+use crate::glob1env::Glob1Env;
+use zeroize::Zeroize;
+
+/// Texte affiché à la place d'une ligne secrète après le verrouillage
+const REDACTED: &str = "[hidden, vault locked]";
 
 #[derive(Clone)]
 pub struct HistoryEntry {
     pub text: String,
     pub is_command: bool,
+    pub is_error: bool,
     pub action: Option<Page>,
+    /// Fichier du Glob1Env ouvert au clic
+    pub open: Option<PathBuf>,
+    /// Ligne lue du coffre ou mot de passe généré, effacée au verrouillage
+    pub secret: bool,
 }
 
 impl Default for HistoryEntry {
@@ -16,7 +27,10 @@ impl Default for HistoryEntry {
         Self {
             text: String::new(),
             is_command: false,
+            is_error: false,
             action: None,
+            open: None,
+            secret: false,
         }
     }
 }
@@ -39,17 +53,26 @@ impl Terminal {
             self.history.push_back(HistoryEntry {
                 text: format!("Welcome to NWTZUI v{}", env!("CARGO_PKG_VERSION")).to_string(),
                 is_command: false,
+                is_error: false,
                 action: None,
+                open: None,
+                secret: false,
             });
             self.history.push_back(HistoryEntry {
                 text: "Type 'help' for a list of commands".to_string(),
                 is_command: false,
+                is_error: false,
                 action: None,
+                open: None,
+                secret: false,
             });
             self.history.push_back(HistoryEntry {
                 text: "".to_string(),
                 is_command: false,
+                is_error: false,
                 action: None,
+                open: None,
+                secret: false,
             });
         }
 
@@ -72,11 +95,16 @@ impl Terminal {
                                 .fill(egui::Color32::from_rgb(0, 0, 0));
 
                             if ui.add(button).clicked() {
+                                if let Some(path) = &entry.open {
+                                    nwtz_ui.glob1env.open(path);
+                                }
                                 ui.ctx().memory_mut(|mem| mem.data.insert_persisted::<Option<Page>>("next_page".into(), Option::from(action.clone())));
                             }
                         } else {
                             let text_color = if entry.is_command {
                                 egui::Color32::CYAN
+                            } else if entry.is_error {
+                                egui::Color32::LIGHT_RED
                             } else {
                                 egui::Color32::from_rgb(255, 255, 255)
                             };
@@ -121,7 +149,7 @@ impl Terminal {
                 || ui.input(|i| i.key_pressed(egui::Key::Enter))
             {
                 if !self.current_input.is_empty() {
-                    if let Some(page) = self.process_command(nwtz_ui.clone(), ui.ctx()) {
+                    if let Some(page) = self.process_command(nwtz_ui, ui.ctx()) {
                         ui.ctx().memory_mut(|mem| {
                             mem.data.insert_persisted::<Option<Page>>(
                                 "next_page".into(), Some(page)
@@ -135,120 +163,142 @@ impl Terminal {
         });
     }
 
-    fn process_command(&mut self, nwtz_ui: NwtzUi, ctx: &egui::Context) -> Option<Page>{
-        let command = self.current_input.clone();
+    fn process_command(&mut self, nwtz_ui: &mut NwtzUi, ctx: &egui::Context) -> Option<Page>{
+        let mut command = self.current_input.clone();
+        let parsed = split_args(&command);
+        let secret = match &parsed {
+            Ok(argv) => is_secret_command(argv),
+            Err(_) => command.contains(".pwd"),
+        };
 
         self.history.push_back(HistoryEntry {
             text: format!("{}{}", PROMPT, command),
             is_command: true,
+            is_error: false,
             action: None,
+            open: None,
+            secret,
         });
         let mut new_page: Option<Page> = None;
 
-        let args:Vec<&str> = command.split(" ").collect();
-
-        match command.as_str() {
-            "help" => {
-                self.add_response( "Available commands:");
-                self.add_response( "  help  - Display this help message");
-                self.add_response( "  clear - Clear terminal history");
-                self.add_response( "  exit  - Exit the application");
-                self.add_response( "  echo <text> - Echo text back to terminal");
-                self.add_response( "  time  - Display current date and time");
-                self.add_response( "  params  - Go to parameters");
-                self.add_clickable("  ▶ Open Settings", Some(Page::Settings));
-                self.add_response( "  env  - Go to environment");
-                self.add_response( "    rd < path > - Read a file");
-                //self.add_response( "    wr < path > < content > - Write to a file");
-                self.add_clickable("  ▶ Open Environment", Some(Page::Glob1Env));
-            },
-            "clear" => {
-                self.history.clear();
-            },
-            "exit" => {
-                self.add_response("Goodbye!");
-                exit(0)
-            },
-            "time" => {
-                let now = chrono::Local::now();
-                self.add_response(&format!("Current time: {}", now.format("%Y-%m-%d %H:%M:%S")));
-            },
-            "params" => {
-                new_page = Some(Page::Settings);
-            },
-            "open" => {
-                println!("{:?}", args);
-            },
-            "minimize" => {
-                self.add_response("Minimizing application...");
-                ctx.memory_mut(|mem| {
-                    mem.data.insert_persisted::<bool>("minimize_request".into(), true);
-                });
-            },
-            "env" => {
-                new_page = Some(Page::Glob1Env);
-            },
-            _ => {
-                if command.starts_with("echo ") {
-                    let echo_text = &command["echo ".len()..];
-                    self.add_response(echo_text);
-                }
-                else if command.starts_with("env ") {
-                    let env_command = &command["env ".len()..];
-                    let args: Vec<&str> = env_command.split_whitespace().collect();
-
-                    match args.get(0).map(|s| *s) {
-                        Some("rd") if args.len() == 2 => {
-                            let path = Path::new(args[1]);
-                            let out = nwtz_ui.glob1env.rd(path).unwrap_or_else(|e| e);
-                            self.add_response(&out);
-                        }
-                        //Some("wr") if args.len() >= 3 => {
-                        //    let path = Path::new(args[1]);
-                        //    let content = args[2..].join(" ");
-//
-                        //    let out = match nwtz_ui.glob1env.wr(path, content) {
-                        //        Ok(_) => format!("Wrote to `{}`", path.display()),
-                        //        Err(e) => format!("Error writing `{}`: {}", path.display(), e),
-                        //    };
-                        //    self.add_response(&out);
-                        //}
-                        _ => {
-                            self.add_response("  env  - Go to environment");
-                            self.add_response("    rd <path>            - Read a file");
-                            //self.add_response("    wr <path> <content>  - Write to a file");
-                        }
-                    }
+        let mut argv = match parsed {
+            Ok(argv) => argv,
+            Err(e) => {
+                self.add_error(&format!("Parse error: {}", e));
+                Vec::new()
+            }
+        };
+        let args: Vec<&str> = argv.iter().map(String::as_str).collect();
+
+        if let Some((name, args)) = args.split_first() {
+            match nwtz_ui.commands.find(name) {
+                Some(handler) => {
+                    let mut command_ctx = CommandContext {
+                        terminal: self,
+                        nwtz_ui,
+                        egui_ctx: ctx,
+                    };
+                    new_page = handler.run(args, &mut command_ctx);
                 }
-                else {
-                    self.add_response(&format!("Unknown command: '{}'", command));
+                None => {
+                    self.add_response(&format!("Unknown command: '{}'", name));
                     self.add_response("Type 'help' for a list of available commands");
                 }
             }
         }
 
+        if secret {
+            argv.zeroize();
+            command.zeroize();
+            self.current_input.zeroize();
+        }
         self.current_input.clear();
         self.scroll_to_bottom = true;
 
         while self.history.len() > MAX_HISTORY_LINES {
-            self.history.pop_front();
+            if let Some(mut entry) = self.history.pop_front()
+                && entry.secret
+            {
+                entry.text.zeroize();
+            }
         }
         new_page
     }
 
-    fn add_response(&mut self, text: &str) {
+    /// Vide l'historique en effaçant les lignes secrètes
+    pub fn clear(&mut self) {
+        for entry in self.history.iter_mut().filter(|entry| entry.secret) {
+            entry.text.zeroize();
+        }
+        self.history.clear();
+    }
+
+    pub fn add_response(&mut self, text: &str) {
+        self.history.push_back(HistoryEntry {
+            text: text.to_string(),
+            is_command: false,
+            is_error: false,
+            action: None,
+            open: None,
+            secret: false,
+        });
+    }
+
+    pub fn add_error(&mut self, text: &str) {
         self.history.push_back(HistoryEntry {
             text: text.to_string(),
             is_command: false,
+            is_error: true,
             action: None,
+            open: None,
+            secret: false,
         });
     }
 
-    fn add_clickable(&mut self, text: &str, target_page: Option<Page>) {
+    pub fn add_clickable(&mut self, text: &str, target_page: Option<Page>) {
         self.history.push_back(HistoryEntry {
             text: text.to_string(),
             is_command: false,
+            is_error: false,
             action: target_page,
+            open: None,
+            secret: false,
+        });
+    }
+
+    /// Ligne cliquable qui ouvre `path` dans le Glob1Env
+    pub fn add_file_link(&mut self, text: &str, path: PathBuf) {
+        self.history.push_back(HistoryEntry {
+            text: text.to_string(),
+            is_command: false,
+            is_error: false,
+            action: Some(Page::Glob1Env),
+            open: Some(path),
+            secret: false,
         });
     }
-}
\ No newline at end of file
+
+    /// Ligne contenant un secret, masquée par `redact_secrets`
+    pub fn add_secret(&mut self, text: &str) {
+        self.history.push_back(HistoryEntry {
+            text: text.to_string(),
+            secret: true,
+            ..Default::default()
+        });
+    }
+
+    /// Efface les secrets affichés, au verrouillage du coffre
+    pub fn redact_secrets(&mut self) {
+        for entry in self.history.iter_mut().filter(|entry| entry.secret) {
+            entry.text.zeroize();
+            entry.text = REDACTED.to_string();
+            entry.secret = false;
+        }
+    }
+}
+
+/// Commande qui peut contenir un secret tapé en clair : `genpwd` ou tout chemin `.pwd`
+fn is_secret_command(argv: &[String]) -> bool {
+    argv.first().is_some_and(|name| name == "genpwd")
+        || argv.iter().any(|arg| Glob1Env::is_protected(Path::new(arg)))
+}
@@ -0,0 +1,218 @@
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use zeroize::Zeroizing;
+use crate::uri::percent_decode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Générateur de codes à usage unique (RFC 6238)
+#[derive(Clone)]
+pub struct Totp {
+    pub service: String,
+    secret: Zeroizing<Vec<u8>>,
+    pub digits: u32,
+    pub period: u64,
+    pub algorithm: Algorithm,
+}
+
+impl Totp {
+    pub fn new(service: &str, secret: Vec<u8>) -> Self {
+        Self {
+            service: service.to_string(),
+            secret: Zeroizing::new(secret),
+            digits: 6,
+            period: 30,
+            algorithm: Algorithm::Sha1,
+        }
+    }
+
+    /// `[TOTP] service::BASE32SECRET`, `[TOTP] service::otpauth://...` ou `[TOTP] otpauth://...`
+    pub fn parse_line(line: &str) -> Option<Result<Self, String>> {
+        let rest = line.strip_prefix("[TOTP]")?.trim();
+        if rest.starts_with("otpauth://") {
+            return Some(Self::from_uri(rest));
+        }
+
+        let (service, value) = rest.split_once("::").unwrap_or((rest, ""));
+        let service = service.trim();
+        let value = value.trim();
+        let totp = if value.starts_with("otpauth://") {
+            Self::from_uri(value).map(|mut totp| {
+                totp.service = service.to_string();
+                totp
+            })
+        } else {
+            base32_decode(value).map(|secret| Self::new(service, secret))
+        };
+        Some(totp)
+    }
+
+    /// `otpauth://totp/Issuer:account?secret=...&algorithm=SHA256&digits=8&period=30`
+    pub fn from_uri(uri: &str) -> Result<Self, String> {
+        let rest = uri.strip_prefix("otpauth://totp/")
+            .ok_or_else(|| "Seules les URI otpauth://totp/ sont supportées".to_string())?;
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let mut secret = None;
+        let mut totp = Self::new(&percent_decode(label), Vec::new());
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(base32_decode(value)?),
+                "digits" => {
+                    totp.digits = value.parse().ok().filter(|d| (6..=8).contains(d))
+                        .ok_or_else(|| format!("Nombre de chiffres invalide : '{}'", value))?;
+                }
+                "period" => {
+                    totp.period = value.parse().ok().filter(|p| *p > 0)
+                        .ok_or_else(|| format!("Période invalide : '{}'", value))?;
+                }
+                "algorithm" => {
+                    totp.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        other => return Err(format!("Algorithme non supporté : '{}'", other)),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        totp.secret = Zeroizing::new(secret.ok_or("Paramètre 'secret' manquant")?);
+        Ok(totp)
+    }
+
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = (unix_time / self.period).to_be_bytes();
+        let hash = match self.algorithm {
+            Algorithm::Sha1 => hmac::<Hmac<Sha1>>(&self.secret, &counter),
+            Algorithm::Sha256 => hmac::<Hmac<Sha256>>(&self.secret, &counter),
+            Algorithm::Sha512 => hmac::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+
+        // Troncature dynamique (RFC 4226, section 5.3)
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+        format!("{:0width$}", binary % 10u32.pow(self.digits), width = self.digits as usize)
+    }
+
+    pub fn code(&self) -> String {
+        self.code_at(now())
+    }
+
+    /// Secondes avant le prochain code
+    pub fn remaining(&self) -> u64 {
+        self.period - now() % self.period
+    }
+}
+
+fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepte toutes les tailles de clé");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Base32 RFC 4648, insensible à la casse, espaces et `=` ignorés
+pub fn base32_decode(input: &str) -> Result<Vec<u8>, String> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = ALPHABET.iter()
+            .position(|a| *a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("Caractère base32 invalide : '{}'", c))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err("Secret TOTP vide".into());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clés de l'annexe B de la RFC 6238 : "1234567890" répété à la taille du condensat
+    fn rfc_totp(algorithm: Algorithm, key_len: usize) -> Totp {
+        let key = b"1234567890".iter().copied().cycle().take(key_len).collect();
+        let mut totp = Totp::new("rfc6238", key);
+        totp.digits = 8;
+        totp.algorithm = algorithm;
+        totp
+    }
+
+    #[test]
+    fn rfc6238_vectors() {
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        let sha1 = rfc_totp(Algorithm::Sha1, 20);
+        let sha256 = rfc_totp(Algorithm::Sha256, 32);
+        let sha512 = rfc_totp(Algorithm::Sha512, 64);
+        for (time, expected1, expected256, expected512) in vectors {
+            assert_eq!(sha1.code_at(time), expected1, "SHA1 T={time}");
+            assert_eq!(sha256.code_at(time), expected256, "SHA256 T={time}");
+            assert_eq!(sha512.code_at(time), expected512, "SHA512 T={time}");
+        }
+    }
+
+    #[test]
+    fn six_digits_keep_leading_zeros() {
+        let mut totp = rfc_totp(Algorithm::Sha1, 20);
+        totp.digits = 6;
+        assert_eq!(totp.code_at(1111111109), "081804");
+    }
+
+    #[test]
+    fn base32_decodes() {
+        assert_eq!(base32_decode("MZXW6YTBOI======").unwrap(), b"foobar");
+        assert_eq!(base32_decode("mzxw 6ytb oi").unwrap(), b"foobar");
+        assert_eq!(base32_decode("JBSWY3DPEHPK3PXP").unwrap(), b"Hello!\xde\xad\xbe\xef");
+        assert_eq!(base32_decode("MZXW1").unwrap_err(), "Caractère base32 invalide : '1'");
+        assert!(base32_decode("").is_err());
+    }
+
+    #[test]
+    fn parses_lines_and_uris() {
+        let totp = Totp::parse_line("[TOTP] mail::JBSWY3DPEHPK3PXP").unwrap().unwrap();
+        assert_eq!(totp.service, "mail");
+        assert_eq!((totp.digits, totp.period, totp.algorithm), (6, 30, Algorithm::Sha1));
+
+        let uri = "otpauth://totp/ACME%20Co:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8&algorithm=SHA1";
+        let totp = Totp::from_uri(uri).unwrap();
+        assert_eq!(totp.service, "ACME Co:alice");
+        assert_eq!(totp.code_at(59), "94287082");
+
+        assert!(Totp::parse_line("[PWD] mail::x").is_none());
+        assert!(Totp::from_uri("otpauth://hotp/x?secret=GEZDGNBV").is_err());
+        assert!(Totp::from_uri("otpauth://totp/x?digits=6").is_err());
+    }
+}
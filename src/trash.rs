@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use crate::fstree::{NodeId, ROOT};
+use crate::glob1env::{Directory, FsEntry, Glob1Env};
+use crate::metadata::{format_time, Metadata};
+use crate::undo::Mutation;
+use crate::vpath::VPath;
+
+/// Répertoire caché où `rm` place les entrées supprimées
+pub const TRASH: &str = "/.trash";
+
+pub struct TrashedEntry {
+    pub path: PathBuf,
+    pub from: Option<PathBuf>,
+    pub deleted: Option<DateTime<Utc>>,
+}
+
+impl TrashedEntry {
+    pub fn label(&self) -> String {
+        let name = self.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        match &self.from {
+            Some(from) => format!("{}  ← {}  ({})", name, from.display(), format_time(self.deleted)),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Vrai pour une entrée de la corbeille, pas pour la corbeille elle-même ; `..` est résolu d'abord
+pub fn in_trash(path: &Path) -> bool {
+    let path = VPath::from(path);
+    path.starts_with(TRASH) && &*path != Path::new(TRASH)
+}
+
+impl Glob1Env {
+    /// La corbeille, créée au premier besoin
+    fn trash_dir(&mut self) -> Result<NodeId, String> {
+        if let Some(id) = self.lookup(Path::new(TRASH)) {
+            return Ok(id);
+        }
+        let name = TRASH.trim_start_matches('/').to_string();
+        self.tree.insert(ROOT, FsEntry::Directory(Directory {
+            name,
+            content: Vec::new(),
+            system: false,
+            metadata: Metadata { hidden: true, ..Metadata::now() },
+            mount: None,
+        }))
+    }
+
+    /// Déplace le nœud `id` dans la corbeille en notant son emplacement d'origine
+    pub fn move_to_trash(&mut self, id: NodeId) -> Result<PathBuf, String> {
+        let (from, to, previous) = self.trash_entry(id)?;
+        self.record(Mutation::Moved { from, to: to.clone(), metadata: previous });
+        Ok(to)
+    }
+
+    /// `move_to_trash` sans l'inscrire dans la pile d'annulation ; renvoie l'ancien et
+    /// le nouveau chemin, et les attributs d'avant
+    pub fn trash_entry(&mut self, id: NodeId) -> Result<(PathBuf, PathBuf, Metadata), String> {
+        let from = self.tree.path(id);
+        let trash = self.trash_dir()?;
+        let name = self.tree.node(id).name().to_string();
+        let (stem, extension) = match name.split_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && !self.tree.node(id).is_dir() => (stem.to_string(), format!(".{extension}")),
+            _ => (name.clone(), String::new()),
+        };
+        let free_name = (1..)
+            .map(|n| match n {
+                1 => name.clone(),
+                n => format!("{stem} {n}{extension}"),
+            })
+            .find(|candidate| self.tree.child(trash, candidate).is_none())
+            .expect("un nom libre finit toujours par être trouvé");
+
+        let previous = self.tree.node(id).metadata().clone();
+        let parent = self.tree.parent(id);
+        self.tree.relocate(id, trash, &free_name)?;
+        let metadata = self.tree.node_mut(id).metadata_mut();
+        metadata.trashed_from = Some(from.clone());
+        metadata.deleted = Some(Utc::now());
+        if let Some(parent) = parent {
+            self.mark_modified(parent);
+        }
+
+        let to = self.tree.path(id);
+        self.moved(&from, None);
+        self.dirty = true;
+        Ok((from, to, previous))
+    }
+
+    pub fn trashed(&self) -> Vec<TrashedEntry> {
+        let Some(trash) = self.lookup(Path::new(TRASH)) else {
+            return Vec::new();
+        };
+        self.tree.children(trash).iter()
+            .map(|&id| {
+                let metadata = self.tree.node(id).metadata();
+                TrashedEntry {
+                    path: self.tree.path(id),
+                    from: metadata.trashed_from.clone(),
+                    deleted: metadata.deleted,
+                }
+            })
+            .collect()
+    }
+
+    /// Remet une entrée de la corbeille à son emplacement d'origine ; renvoie ce chemin.
+    /// Seuls les enfants directs de la corbeille sont acceptés.
+    pub fn restore_trashed(&mut self, path: &Path) -> Result<PathBuf, String> {
+        let path = VPath::from(path);
+        let id = self.lookup(&path)
+            .filter(|_| path.parent().is_some_and(|parent| &*parent == Path::new(TRASH)))
+            .ok_or_else(|| format!("Pas dans la corbeille : '{}'", path.display()))?;
+        let from = self.tree.node(id).metadata().trashed_from.clone()
+            .ok_or_else(|| format!("Emplacement d'origine inconnu : '{}'", path.display()))?;
+        if self.exists(&from) {
+            return Err(format!("'{}' existe déjà", from.display()));
+        }
+        let parent = from.parent().and_then(|parent| self.lookup(parent))
+            .filter(|&parent| self.tree.node(parent).is_dir())
+            .ok_or_else(|| format!("Répertoire d'origine introuvable : '{}'", from.display()))?;
+        self.writable_dir(parent)?;
+
+        let previous = self.tree.node(id).metadata().clone();
+        let name = from.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        self.tree.relocate(id, parent, &name)?;
+        let metadata = self.tree.node_mut(id).metadata_mut();
+        metadata.trashed_from = None;
+        metadata.deleted = None;
+        self.mark_modified(parent);
+
+        self.record(Mutation::Moved { from: path.to_path_buf(), to: from.clone(), metadata: previous });
+        self.moved(&path, Some(&from));
+        self.dirty = true;
+        Ok(from)
+    }
+
+    /// Supprime définitivement le contenu de la corbeille ; ce n'est pas annulable
+    pub fn empty_trash(&mut self) -> usize {
+        let Some(trash) = self.lookup(Path::new(TRASH)) else {
+            return 0;
+        };
+        let children = self.tree.children(trash).to_vec();
+        for &id in &children {
+            let path = self.tree.path(id);
+            self.tree.remove(id);
+            self.moved(&path, None);
+        }
+        self.dirty |= !children.is_empty();
+        children.len()
+    }
+}
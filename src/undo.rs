@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use egui::{Key, KeyboardShortcut, Modifiers};
+use crate::fstree::{Node, NodeId};
+use crate::glob1env::{split_components, Glob1Env};
+use crate::history::Revision;
+use crate::metadata::Metadata;
+
+pub const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+
+/// Nombre de modifications annulables
+pub const MAX_UNDO: usize = 50;
+
+/// Modification de l'arborescence, avec de quoi la défaire
+#[derive(Clone, Debug)]
+pub enum Mutation {
+    /// Entrée créée ou copiée
+    Created(PathBuf),
+    /// Contenu remplacé ; `previous` est la version ajoutée à l'historique par l'écriture
+    Written { path: PathBuf, previous: Revision },
+    /// Déplacement, renommage, mise à la corbeille ou restauration
+    Moved { from: PathBuf, to: PathBuf, metadata: Metadata },
+    Flags { path: PathBuf, previous: Metadata },
+}
+
+impl Mutation {
+    fn label(&self) -> String {
+        match self {
+            Mutation::Created(path) => format!("creation of '{}'", path.display()),
+            Mutation::Written { path, .. } => format!("write to '{}'", path.display()),
+            Mutation::Moved { from, to, .. } => format!("move of '{}' to '{}'", from.display(), to.display()),
+            Mutation::Flags { path, .. } => format!("flags of '{}'", path.display()),
+        }
+    }
+}
+
+impl Glob1Env {
+    pub fn record(&mut self, mutation: Mutation) {
+        self.undo_stack.push_back(mutation);
+        if self.undo_stack.len() > MAX_UNDO {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Défait la dernière modification et la décrit. Une modification devenue impossible
+    /// (entrée supprimée définitivement entre-temps) est retirée de la pile.
+    pub fn undo(&mut self) -> Result<String, String> {
+        let mutation = self.undo_stack.pop_back().ok_or("Rien à annuler")?;
+        let label = mutation.label();
+        match mutation {
+            Mutation::Created(path) => {
+                // Comme `rm` : mêmes protections, et l'entrée part à la corbeille
+                self.existing(&path)?;
+                let id = self.mutable_node(&path)?;
+                self.trash_entry(id)?;
+            }
+            Mutation::Written { path, previous } => {
+                let id = self.existing(&path)?;
+                // La version de cette écriture n'est la dernière que si le fichier n'a pas été réécrit depuis
+                match self.tree.node_mut(id) {
+                    Node::File(f) if f.history.last().is_some_and(|r| r.time == previous.time && r.content == previous.content) => {
+                        f.history.pop();
+                        f.content = previous.content;
+                    }
+                    _ => return Err(format!("Impossible d'annuler : '{}' a été modifié depuis", path.display())),
+                }
+                self.mark_modified(id);
+            }
+            Mutation::Moved { from, to, metadata } => {
+                // Comme `mv` : l'entrée et les deux répertoires doivent être modifiables
+                self.existing(&to)?;
+                let id = self.mutable_node(&to)?;
+                let parts = split_components(&from);
+                let (dir_parts, name) = parts.split_at(parts.len() - 1);
+                let dir = self.tree.lookup(dir_parts)
+                    .filter(|&dir| self.tree.node(dir).is_dir())
+                    .ok_or_else(|| format!("Répertoire introuvable : '{}'", from.display()))?;
+                self.writable_dir(dir)?;
+                let old_parent = self.tree.parent(id);
+                self.tree.relocate(id, dir, name[0])?;
+                *self.tree.node_mut(id).metadata_mut() = metadata;
+                for parent in old_parent.into_iter().chain([dir]) {
+                    self.mark_modified(parent);
+                }
+                self.moved(&to, Some(&from));
+            }
+            Mutation::Flags { path, previous } => {
+                let id = self.existing(&path)?;
+                *self.tree.node_mut(id).metadata_mut() = previous;
+            }
+        }
+        self.dirty = true;
+        Ok(format!("Undid {}", label))
+    }
+
+    fn existing(&self, path: &Path) -> Result<NodeId, String> {
+        self.lookup(path).ok_or_else(|| format!("Impossible d'annuler : '{}' n'existe plus", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Origin;
+    use crate::trash::{in_trash, TRASH};
+    use crate::vault::Vault;
+    use crate::vpath::VPath;
+
+    fn env() -> Glob1Env {
+        let mut env = Glob1Env::new(None, Vault::default());
+        env.mkdir(Path::new("/a")).unwrap();
+        env.mkdir(Path::new("/b")).unwrap();
+        env.wr(Path::new("/a/f.txt"), "x".to_string(), Origin::Terminal).unwrap();
+        env.undo_stack.clear();
+        env
+    }
+
+    fn set_readonly(env: &mut Glob1Env, path: &str) {
+        let id = env.lookup(Path::new(path)).unwrap();
+        env.tree.node_mut(id).metadata_mut().readonly = true;
+    }
+
+    #[test]
+    fn undo_move_is_refused_into_readonly_dir() {
+        let mut env = env();
+        env.mv(Path::new("/a/f.txt"), Path::new("/b")).unwrap();
+        set_readonly(&mut env, "/a");
+        assert!(env.undo().is_err());
+        assert!(env.exists(Path::new("/b/f.txt")));
+
+        env.mv(Path::new("/b/f.txt"), Path::new("/b/g.txt")).unwrap();
+        assert!(env.undo().is_ok());
+        assert!(env.exists(Path::new("/b/f.txt")));
+    }
+
+    #[test]
+    fn undo_rm_is_refused_into_readonly_dir() {
+        let mut env = env();
+        env.rm(Path::new("/a/f.txt"), false).unwrap();
+        set_readonly(&mut env, "/a");
+        assert!(env.undo().is_err());
+        assert!(!env.exists(Path::new("/a/f.txt")));
+    }
+
+    #[test]
+    fn restore_only_takes_trash_children() {
+        let mut env = env();
+        env.rm(Path::new("/a/f.txt"), false).unwrap();
+        // Une entrée hors de la corbeille qui porte encore son emplacement d'origine
+        env.wr(Path::new("/b/g.txt"), "y".to_string(), Origin::Terminal).unwrap();
+        let id = env.lookup(Path::new("/b/g.txt")).unwrap();
+        env.tree.node_mut(id).metadata_mut().trashed_from = Some("/a/g.txt".into());
+
+        assert!(VPath::from(TRASH).join("../b/g.txt").is_err());
+        assert!(VPath::from(TRASH).join("..").is_err());
+        assert!(env.restore_trashed(Path::new("/.trash/../b/g.txt")).is_err());
+        assert!(env.restore_trashed(Path::new(TRASH)).is_err());
+        assert!(env.exists(Path::new("/b/g.txt")));
+        assert!(!in_trash(Path::new("/.trash/../b/g.txt")));
+
+        let restored = env.restore_trashed(&VPath::from(TRASH).join("f.txt").unwrap()).unwrap();
+        assert_eq!(restored, Path::new("/a/f.txt"));
+    }
+}
@@ -0,0 +1,47 @@
+/// Décode les échappements `%XX` d'une URI (RFC 3986). `+` reste un `+` :
+/// il ne vaut une espace que dans les formulaires HTML.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // `from_str_radix` accepterait un signe (`%+1`) : seuls deux chiffres hexadécimaux comptent
+        let hex = bytes.get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(percent_decode("ACME%20Co%3Aalice"), "ACME Co:alice");
+        assert_eq!(percent_decode("%c3%a9t%C3%A9"), "été");
+        assert_eq!(percent_decode("a+b"), "a+b");
+        assert_eq!(percent_decode(""), "");
+    }
+
+    #[test]
+    fn leaves_invalid_escapes() {
+        assert_eq!(percent_decode("%+1"), "%+1");
+        assert_eq!(percent_decode("%-1x"), "%-1x");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%%41"), "%A");
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use egui::RichText;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+use crate::storage;
+
+/// Préfixe du contenu chiffré d'un fichier `.pwd`
+pub const SEALED_PREFIX: &str = "nwtzvault:1:";
+
+const CHECK_PLAINTEXT: &[u8] = b"nwtzui-vault-check";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct VaultHeader {
+    version: u32,
+    /// Argon2id : mémoire en KiB, itérations, parallélisme
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    /// `CHECK_PLAINTEXT` chiffré, pour vérifier le mot de passe maître
+    check: String,
+}
+
+/// Coffre des secrets : la clé dérivée du mot de passe maître ne vit qu'en mémoire,
+/// et seulement entre `unlock` et `lock`.
+#[derive(Clone, Default)]
+pub struct Vault {
+    header_path: Option<PathBuf>,
+    header: Option<VaultHeader>,
+    /// En-tête présent mais illisible
+    load_error: Option<String>,
+    key: Option<Arc<Zeroizing<[u8; KEY_LEN]>>>,
+}
+
+impl fmt::Debug for Vault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vault")
+            .field("initialized", &self.is_initialized())
+            .field("unlocked", &self.is_unlocked())
+            .finish()
+    }
+}
+
+impl Vault {
+    pub fn load(header_path: Option<PathBuf>) -> Self {
+        let mut load_error = None;
+        let header = header_path.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| match serde_json::from_str(&raw) {
+                Ok(header) => Some(header),
+                Err(e) => {
+                    load_error = Some(format!("En-tête du coffre invalide : {}", e));
+                    None
+                }
+            });
+        Self { header_path, header, load_error, key: None }
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.header.is_some()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Crée le coffre avec un nouveau mot de passe maître et le laisse déverrouillé
+    pub fn create(&mut self, password: &str) -> Result<(), String> {
+        if self.is_initialized() {
+            return Err("Le coffre existe déjà".into());
+        }
+        if password.is_empty() {
+            return Err("Le mot de passe maître ne peut pas être vide".into());
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let params = Params::default();
+        let key = derive_key(password, &salt, params.m_cost(), params.t_cost(), params.p_cost())?;
+        let check = seal(&key, CHECK_PLAINTEXT)?;
+
+        let header = VaultHeader {
+            version: 1,
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+            salt: BASE64.encode(salt),
+            check,
+        };
+        if let Some(path) = &self.header_path {
+            let data = serde_json::to_vec_pretty(&header).map_err(|e| e.to_string())?;
+            storage::write_atomic(path, &data)?;
+        }
+
+        self.header = Some(header);
+        self.load_error = None;
+        self.key = Some(Arc::new(key));
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, password: &str) -> Result<(), String> {
+        let header = self.header.as_ref().ok_or("Aucun coffre n'a été créé")?;
+        let salt = BASE64.decode(&header.salt).map_err(|e| e.to_string())?;
+        let key = derive_key(password, &salt, header.m_cost, header.t_cost, header.p_cost)?;
+
+        match open(&key, &header.check) {
+            Ok(check) if check.as_slice() == CHECK_PLAINTEXT => {
+                self.key = Some(Arc::new(key));
+                Ok(())
+            }
+            _ => Err("Mot de passe maître incorrect".into()),
+        }
+    }
+
+    pub fn lock(&mut self) {
+        self.key = None;
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        let key = self.key.as_ref().ok_or("Coffre verrouillé")?;
+        seal(key, plaintext.as_bytes())
+    }
+
+    /// Déchiffre un contenu `.pwd`. Un contenu sans préfixe (ancien fichier en clair)
+    /// est rendu tel quel pour pouvoir être rechiffré.
+    pub fn decrypt(&self, content: &str) -> Result<Zeroizing<String>, String> {
+        if !is_sealed(content) {
+            return Ok(Zeroizing::new(content.to_string()));
+        }
+        let key = self.key.as_ref().ok_or("Coffre verrouillé")?;
+        let plaintext = open(key, content)?;
+        std::str::from_utf8(&plaintext)
+            .map(|s| Zeroizing::new(s.to_string()))
+            .map_err(|_| "Contenu déchiffré invalide".to_string())
+    }
+}
+
+pub fn is_sealed(content: &str) -> bool {
+    content.starts_with(SEALED_PREFIX)
+}
+
+fn derive_key(password: &str, salt: &[u8], m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Zeroizing<[u8; KEY_LEN]>, String> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_LEN)).map_err(|e| e.to_string())?;
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn seal(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<String, String> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(|_| "Chiffrement impossible".to_string())?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(format!("{SEALED_PREFIX}{}", BASE64.encode(blob)))
+}
+
+fn open(key: &[u8; KEY_LEN], sealed: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let encoded = sealed.trim().strip_prefix(SEALED_PREFIX).ok_or("Contenu non chiffré")?;
+    let blob = BASE64.decode(encoded).map_err(|_| "Contenu chiffré corrompu".to_string())?;
+    if blob.len() < NONCE_LEN {
+        return Err("Contenu chiffré corrompu".into());
+    }
+
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| "Déchiffrement impossible (clé incorrecte ou contenu altéré)".to_string())
+}
+
+/// Formulaire de création / déverrouillage du coffre
+#[derive(Clone, Default)]
+pub struct UnlockForm {
+    password: Zeroizing<String>,
+    confirm: Zeroizing<String>,
+    /// Accepte de perdre les fichiers chiffrés avec l'ancien coffre
+    discard_sealed: bool,
+    error: Option<String>,
+}
+
+impl fmt::Debug for UnlockForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnlockForm").field("error", &self.error).finish_non_exhaustive()
+    }
+}
+
+impl UnlockForm {
+    /// Affiche le formulaire ; renvoie `true` quand le coffre vient d'être déverrouillé.
+    /// `sealed_files` compte les fichiers déjà chiffrés : sans leur coffre, en créer un
+    /// nouveau les rend illisibles, ce qui doit être confirmé.
+    pub fn ui(&mut self, ui: &mut egui::Ui, vault: &mut Vault, sealed_files: usize) -> bool {
+        let creating = !vault.is_initialized();
+        let mut submitted = false;
+
+        ui.vertical_centered(|ui| {
+            ui.label(RichText::new(if creating { "🔒 Create a master password" } else { "🔒 Vault locked" }).size(14.0));
+            ui.add_space(4.0);
+
+            let response = ui.add(egui::TextEdit::singleline(&mut *self.password).password(true).hint_text("Master password"));
+            submitted |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if creating {
+                let response = ui.add(egui::TextEdit::singleline(&mut *self.confirm).password(true).hint_text("Confirm"));
+                submitted |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                if let Some(error) = &vault.load_error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                if sealed_files > 0 {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_RED,
+                        format!("{sealed_files} encrypted file(s) belong to a missing vault and will stay unreadable"),
+                    );
+                    ui.checkbox(&mut self.discard_sealed, "Create a new vault anyway");
+                }
+            }
+
+            submitted |= ui.button(RichText::new(if creating { "create" } else { "unlock" }).size(14.0)).clicked();
+
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::LIGHT_RED, error);
+            }
+        });
+
+        if !submitted {
+            return false;
+        }
+
+        let result = if creating {
+            if *self.password != *self.confirm {
+                Err("Les mots de passe ne correspondent pas".to_string())
+            } else if sealed_files > 0 && !self.discard_sealed {
+                Err("Restaurez vault.json ou confirmez la création d'un nouveau coffre".to_string())
+            } else {
+                vault.create(&self.password)
+            }
+        } else {
+            vault.unlock(&self.password)
+        };
+
+        *self = Self::default();
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coffre en mémoire avec des paramètres Argon2 minimaux pour garder les tests rapides
+    fn new_vault(password: &str) -> Vault {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(password, &salt, 8, 1, 1).unwrap();
+        let header = VaultHeader {
+            version: 1,
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+            salt: BASE64.encode(salt),
+            check: seal(&key, CHECK_PLAINTEXT).unwrap(),
+        };
+        Vault { header_path: None, header: Some(header), load_error: None, key: Some(Arc::new(key)) }
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let vault = new_vault("master");
+        let plaintext = "[PWD] mail::hunter2\n[TOTP] mail::JBSWY3DPEHPK3PXP\n";
+        let sealed = vault.encrypt(plaintext).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("hunter2"));
+        assert_eq!(vault.decrypt(&sealed).unwrap().as_str(), plaintext);
+        assert_ne!(vault.encrypt(plaintext).unwrap(), sealed, "chaque scellement tire un nouveau nonce");
+    }
+
+    #[test]
+    fn unlock_after_lock() {
+        let mut vault = new_vault("master");
+        let sealed = vault.encrypt("secret").unwrap();
+        vault.lock();
+        assert!(!vault.is_unlocked());
+        assert_eq!(vault.decrypt(&sealed).unwrap_err(), "Coffre verrouillé");
+        vault.unlock("master").unwrap();
+        assert_eq!(vault.decrypt(&sealed).unwrap().as_str(), "secret");
+    }
+
+    #[test]
+    fn wrong_password_is_rejected() {
+        let mut vault = new_vault("master");
+        let sealed = vault.encrypt("secret").unwrap();
+        vault.lock();
+        assert_eq!(vault.unlock("Master").unwrap_err(), "Mot de passe maître incorrect");
+        assert!(!vault.is_unlocked());
+
+        // Une autre clé ne déchiffre pas le contenu
+        let other = new_vault("master");
+        assert!(other.decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn tampered_content_is_rejected() {
+        let vault = new_vault("master");
+        let sealed = vault.encrypt("secret").unwrap();
+        let mut blob = BASE64.decode(&sealed[SEALED_PREFIX.len()..]).unwrap();
+        *blob.last_mut().unwrap() ^= 1;
+        let tampered = format!("{SEALED_PREFIX}{}", BASE64.encode(blob));
+        assert!(vault.decrypt(&tampered).is_err());
+        assert!(vault.decrypt(&format!("{SEALED_PREFIX}AAAA")).is_err());
+    }
+
+    #[test]
+    fn corrupt_header_is_reported() {
+        let path = std::env::temp_dir().join(format!("nwtzui-vault-{}.json", std::process::id()));
+        std::fs::write(&path, "{ not json").unwrap();
+        let vault = Vault::load(Some(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(!vault.is_initialized());
+        assert!(vault.load_error.is_some());
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+
+/// Chemin absolu et normalisé du Glob1Env : commence par `/`, sans `.`, `..` ni `//`.
+/// `..` à la racine reste à la racine.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VPath(PathBuf);
+
+impl Default for VPath {
+    fn default() -> Self {
+        Self::root()
+    }
+}
+
+impl VPath {
+    pub fn root() -> Self {
+        Self(PathBuf::from("/"))
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0 == Path::new("/")
+    }
+
+    /// `input` relatif à `self`, ou absolu s'il commence par `/`
+    pub fn resolve(&self, input: &str) -> Self {
+        if input.starts_with('/') {
+            Self::from(input)
+        } else {
+            Self::normalize(self.parts().into_iter().chain(input.split('/')))
+        }
+    }
+
+    /// Ajoute un nom d'entrée, refusé s'il est vide, `.`, `..` ou contient `/`
+    pub fn join(&self, name: &str) -> Result<Self, String> {
+        validate_name(name)?;
+        let mut path = self.0.clone();
+        path.push(name);
+        Ok(Self(path))
+    }
+
+    /// Répertoire parent, `None` à la racine
+    pub fn parent(&self) -> Option<Self> {
+        self.0.parent().map(|parent| Self(parent.to_path_buf()))
+    }
+
+    /// Remonte d'un niveau ; faux si déjà à la racine
+    pub fn pop(&mut self) -> bool {
+        self.0.pop()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.file_name().and_then(|name| name.to_str())
+    }
+
+    pub fn parts(&self) -> Vec<&str> {
+        self.0.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn normalize<'a>(segments: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut parts: Vec<&str> = Vec::new();
+        for segment in segments {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                name => parts.push(name),
+            }
+        }
+        let mut path = PathBuf::from("/");
+        path.extend(parts);
+        Self(path)
+    }
+}
+
+/// Nom d'un fichier ou d'un répertoire
+pub fn validate_name(name: &str) -> Result<(), String> {
+    match name {
+        "" => Err("Nom vide".into()),
+        "." | ".." => Err(format!("Nom réservé : '{}'", name)),
+        name if name.contains('/') => Err(format!("Nom invalide (contient '/') : '{}'", name)),
+        _ => Ok(()),
+    }
+}
+
+/// Un chemin relatif est pris depuis la racine
+impl From<&str> for VPath {
+    fn from(path: &str) -> Self {
+        Self::normalize(path.split('/'))
+    }
+}
+
+impl From<&Path> for VPath {
+    fn from(path: &Path) -> Self {
+        Self::from(path.to_string_lossy().as_ref())
+    }
+}
+
+impl From<PathBuf> for VPath {
+    fn from(path: PathBuf) -> Self {
+        Self::from(path.as_path())
+    }
+}
+
+impl From<VPath> for PathBuf {
+    fn from(path: VPath) -> Self {
+        path.0
+    }
+}
+
+impl Deref for VPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for VPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl fmt::Display for VPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vpath(path: &str) -> VPath {
+        VPath::from(path)
+    }
+
+    #[test]
+    fn root_is_default() {
+        assert_eq!(VPath::default(), VPath::root());
+        assert!(VPath::root().is_root());
+        assert_eq!(VPath::root().to_string(), "/");
+        assert!(!vpath("/a").is_root());
+    }
+
+    #[test]
+    fn from_normalizes() {
+        assert_eq!(vpath("/a/b").to_string(), "/a/b");
+        assert_eq!(vpath("a/b").to_string(), "/a/b");
+        assert_eq!(vpath("//a///b/").to_string(), "/a/b");
+        assert_eq!(vpath("/a/./b/.").to_string(), "/a/b");
+        assert_eq!(vpath("/a/b/../c").to_string(), "/a/c");
+        assert_eq!(vpath("/a/b/../..").to_string(), "/");
+        assert_eq!(vpath("").to_string(), "/");
+        assert_eq!(vpath(".").to_string(), "/");
+    }
+
+    #[test]
+    fn dotdot_is_clamped_at_root() {
+        assert_eq!(vpath("/..").to_string(), "/");
+        assert_eq!(vpath("/../../a").to_string(), "/a");
+        assert_eq!(vpath("/a/../../b").to_string(), "/b");
+        assert_eq!(VPath::root().resolve("../../..").to_string(), "/");
+        assert_eq!(vpath("/a").resolve("../../b").to_string(), "/b");
+    }
+
+    #[test]
+    fn resolve_relative() {
+        let cwd = vpath("/home/user");
+        assert_eq!(cwd.resolve("docs").to_string(), "/home/user/docs");
+        assert_eq!(cwd.resolve("./docs/").to_string(), "/home/user/docs");
+        assert_eq!(cwd.resolve("..").to_string(), "/home");
+        assert_eq!(cwd.resolve("../other/./x").to_string(), "/home/other/x");
+        assert_eq!(cwd.resolve(".").to_string(), "/home/user");
+        assert_eq!(cwd.resolve("").to_string(), "/home/user");
+    }
+
+    #[test]
+    fn resolve_absolute() {
+        let cwd = vpath("/home/user");
+        assert_eq!(cwd.resolve("/etc").to_string(), "/etc");
+        assert_eq!(cwd.resolve("/etc/../tmp").to_string(), "/tmp");
+        assert_eq!(cwd.resolve("/").to_string(), "/");
+    }
+
+    #[test]
+    fn join_appends_a_name() {
+        assert_eq!(VPath::root().join("a").unwrap().to_string(), "/a");
+        assert_eq!(vpath("/a").join("b.txt").unwrap().to_string(), "/a/b.txt");
+        assert_eq!(vpath("/a").join("with space").unwrap().to_string(), "/a/with space");
+    }
+
+    #[test]
+    fn join_rejects_invalid_names() {
+        let dir = vpath("/a");
+        assert!(dir.join("").is_err());
+        assert!(dir.join(".").is_err());
+        assert!(dir.join("..").is_err());
+        assert!(dir.join("b/c").is_err());
+        assert!(dir.join("/b").is_err());
+    }
+
+    #[test]
+    fn parent_and_pop() {
+        assert_eq!(vpath("/a/b").parent(), Some(vpath("/a")));
+        assert_eq!(vpath("/a").parent(), Some(VPath::root()));
+        assert_eq!(VPath::root().parent(), None);
+
+        let mut path = vpath("/a/b");
+        assert!(path.pop());
+        assert_eq!(path, vpath("/a"));
+        assert!(path.pop());
+        assert!(path.is_root());
+        assert!(!path.pop());
+        assert!(path.is_root());
+    }
+
+    #[test]
+    fn name_and_parts() {
+        assert_eq!(vpath("/a/b.txt").name(), Some("b.txt"));
+        assert_eq!(VPath::root().name(), None);
+        assert_eq!(vpath("/a/b/c").parts(), vec!["a", "b", "c"]);
+        assert!(VPath::root().parts().is_empty());
+    }
+
+    #[test]
+    fn path_conversions() {
+        assert_eq!(VPath::from(Path::new("/a/../b")), vpath("/b"));
+        assert_eq!(VPath::from(PathBuf::from("a/./b")), vpath("/a/b"));
+        assert_eq!(PathBuf::from(vpath("/a/b")), PathBuf::from("/a/b"));
+        assert_eq!(&*vpath("/a"), Path::new("/a"));
+    }
+
+    #[test]
+    fn validate_name_rules() {
+        assert!(validate_name("file.txt").is_ok());
+        assert!(validate_name(".hidden").is_ok());
+        assert!(validate_name("...").is_ok());
+        assert_eq!(validate_name(""), Err("Nom vide".to_string()));
+        assert!(validate_name(".").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("/").is_err());
+        assert!(validate_name("a/").is_err());
+    }
+}
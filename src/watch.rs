@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::Local;
+use egui_inbox::UiInbox;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::glob1env::Glob1Env;
+use crate::mount::Mount;
+
+/// Délai sans nouvel événement avant de relire un répertoire modifié sur l'hôte
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Changement d'une entrée montée, vu depuis le Glob1Env
+#[derive(Clone, Debug)]
+pub struct ChangeEvent {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+}
+
+/// Surveillance inotify des répertoires montés. Les événements de l'hôte arrivent
+/// par l'inbox et sont regroupés par chemin jusqu'à `DEBOUNCE` de calme.
+#[derive(Clone, Default)]
+pub struct HostWatch {
+    inbox: Arc<UiInbox<(PathBuf, ChangeKind)>>,
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    /// Racines de l'hôte surveillées
+    roots: Vec<PathBuf>,
+    /// Points de montage du Glob1Env, relevés avec `roots`
+    mounts: Vec<(PathBuf, Mount)>,
+    /// Génération de l'arborescence à laquelle `roots` et `mounts` ont été calculés
+    generation: Option<u64>,
+    pending: HashMap<PathBuf, (ChangeKind, Instant)>,
+    /// Chemins suivis par le script en cours avec `watch()` ; leurs changements sont
+    /// seulement affichés sous le script, qui n'en est pas notifié
+    pub subscriptions: Vec<PathBuf>,
+}
+
+impl fmt::Debug for HostWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostWatch")
+            .field("roots", &self.roots)
+            .field("pending", &self.pending.len())
+            .field("subscriptions", &self.subscriptions)
+            .finish()
+    }
+}
+
+impl ChangeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Removed => "removed",
+        }
+    }
+
+    /// Résultat de `self` suivi de `next` sur le même chemin pendant le délai
+    fn then(self, next: ChangeKind) -> ChangeKind {
+        match (self, next) {
+            (ChangeKind::Created, ChangeKind::Modified) => ChangeKind::Created,
+            (ChangeKind::Removed, ChangeKind::Created) => ChangeKind::Modified,
+            (_, next) => next,
+        }
+    }
+}
+
+impl ChangeEvent {
+    /// Ligne affichée sous la sortie d'un script abonné
+    pub fn message(&self) -> String {
+        format!("[CHANGE] {} {} {}", Local::now().format("%H:%M:%S"), self.kind.label(), self.path.display())
+    }
+}
+
+/// Changements décrits par un événement notify ; les accès sont ignorés
+fn changes(event: Event) -> Vec<(PathBuf, ChangeKind)> {
+    let kinds: Vec<ChangeKind> = match event.kind {
+        EventKind::Create(_) => vec![ChangeKind::Created],
+        EventKind::Remove(_) => vec![ChangeKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => vec![ChangeKind::Removed],
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => vec![ChangeKind::Created],
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => vec![ChangeKind::Removed, ChangeKind::Created],
+        EventKind::Modify(_) => vec![ChangeKind::Modified],
+        _ => return Vec::new(),
+    };
+    // Un renommage complet donne l'ancien puis le nouveau chemin
+    match kinds.as_slice() {
+        [kind] => event.paths.into_iter().map(|path| (path, *kind)).collect(),
+        _ => event.paths.into_iter().zip(kinds).collect(),
+    }
+}
+
+impl HostWatch {
+    /// Surveille exactement `roots`, en créant le watcher au premier montage
+    fn set_roots(&mut self, roots: Vec<PathBuf>) -> Result<(), String> {
+        let mut guard = self.watcher.lock().map_err(|_| "Surveillance indisponible")?;
+        if guard.is_none() {
+            if roots.is_empty() {
+                self.roots = roots;
+                return Ok(());
+            }
+            let sender = self.inbox.sender();
+            let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+                if let Ok(event) = event {
+                    for change in changes(event) {
+                        let _ = sender.send(change);
+                    }
+                }
+            }).map_err(|e| format!("Surveillance impossible : {}", e))?;
+            *guard = Some(watcher);
+        }
+        let watcher = guard.as_mut().expect("watcher créé ci-dessus");
+
+        for root in self.roots.iter().filter(|root| !roots.contains(root)) {
+            let _ = watcher.unwatch(root);
+        }
+        let mut errors = Vec::new();
+        for root in roots.iter().filter(|root| !self.roots.contains(root)) {
+            if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+                errors.push(format!("Surveillance de '{}' impossible : {}", root.display(), e));
+            }
+        }
+        self.roots = roots;
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("\n")),
+        }
+    }
+}
+
+impl Glob1Env {
+    /// Relit les répertoires changés sur l'hôte une fois le délai écoulé,
+    /// et affiche les changements sous le script en cours s'il s'y est abonné
+    pub fn poll_changes(&mut self, ctx: &egui::Context) {
+        // Les montages ne sont recherchés dans l'arborescence que lorsqu'elle change
+        if self.watch.generation != Some(self.tree.generation()) {
+            self.watch.mounts = self.mounts();
+            let mut roots: Vec<PathBuf> = self.watch.mounts.iter().map(|(_, mount)| mount.host.clone()).collect();
+            roots.sort();
+            roots.dedup();
+            if let Err(e) = self.watch.set_roots(roots) {
+                eprintln!("{}", e);
+            }
+            self.watch.generation = Some(self.tree.generation());
+        }
+
+        let now = Instant::now();
+        for (host, kind) in self.watch.inbox.read(ctx) {
+            let Some(path) = self.watch.mounts.iter().find_map(|(at, mount)| Some(at.join(host.strip_prefix(&mount.host).ok()?))) else {
+                continue;
+            };
+            let entry = self.watch.pending.entry(path).or_insert((kind, now));
+            *entry = (entry.0.then(kind), now);
+        }
+
+        let ready: Vec<ChangeEvent> = self.watch.pending.iter()
+            .filter(|(_, (_, last))| now.duration_since(*last) >= DEBOUNCE)
+            .map(|(path, (kind, _))| ChangeEvent { path: path.clone(), kind: *kind })
+            .collect();
+        for event in &ready {
+            self.watch.pending.remove(&event.path);
+        }
+        if !self.watch.pending.is_empty() {
+            ctx.request_repaint_after(DEBOUNCE);
+        }
+        if ready.is_empty() {
+            return;
+        }
+
+        let mut parents: Vec<&Path> = ready.iter().filter_map(|event| event.path.parent()).collect();
+        parents.sort();
+        parents.dedup();
+        for parent in parents {
+            // Un répertoire pas encore listé le sera à sa prochaine ouverture
+            if self.is_dir(parent) {
+                let _ = self.sync_path(parent);
+            }
+        }
+
+        for event in ready {
+            if self.execution_started && self.watch.subscriptions.iter().any(|path| event.path.starts_with(path)) {
+                self.message_buffer.push(event.message());
+            }
+        }
+    }
+}